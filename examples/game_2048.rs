@@ -1,144 +1,46 @@
-use bellpepper_core::{num::AllocatedNum, SynthesisError};
-use ff::{Field, PrimeField};
-
 use flate2::{write::ZlibEncoder, Compression};
 use nova_2048::{
-    can_move::CanMove, direction_chooser::DirectionChooser, gen_next::GenNext, merge::Merge,
-    restore::Restore, sort::SortByZero,
-};
-use nova_snark::{
-    traits::{
-        circuit::{StepCircuit, TrivialCircuit},
-        snark::default_ck_hint,
-        Group,
-    },
-    CompressedSNARK, PublicParams, RecursiveSNARK,
+    bundle::ProofBundle,
+    circuit::GameConfig,
+    game::{player_id, Direction, Game},
+    prover::GameProver,
+    verifier::{verify_game, GameVerifier},
 };
-use std::time::Instant;
+use nova_snark::traits::Group;
+use std::{io::Write, time::Instant};
 
 type E1 = pasta_curves::pallas::Point;
 type E2 = pasta_curves::vesta::Point;
+type EE1 = nova_snark::provider::ipa_pc::EvaluationEngine<E1>;
+type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
+type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>;
+type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>;
 
-const ZERO: <E1 as Group>::Scalar = <E1 as Group>::Scalar::ZERO;
-const ONE: <E1 as Group>::Scalar = <E1 as Group>::Scalar::ONE;
-const UP: [<E1 as Group>::Scalar; 4] = [ONE, ZERO, ZERO, ZERO];
-const DOWN: [<E1 as Group>::Scalar; 4] = [ZERO, ONE, ZERO, ZERO];
-const LEFT: [<E1 as Group>::Scalar; 4] = [ZERO, ZERO, ONE, ZERO];
-const RIGHT: [<E1 as Group>::Scalar; 4] = [ZERO, ZERO, ZERO, ONE];
-
-#[derive(Debug, Clone)]
-pub struct Game2048Circuit<F: PrimeField> {
-    pub directions: Vec<[F; 4]>,
-}
-
-impl<F: PrimeField> Game2048Circuit<F> {
-    pub fn new(directions: &[[F; 4]]) -> Self {
-        Self {
-            directions: directions.to_vec(),
-        }
-    }
-}
-
-impl<F: PrimeField> StepCircuit<F> for Game2048Circuit<F> {
-    fn arity(&self) -> usize {
-        16
-    }
-
-    fn synthesize<CS: bellpepper_core::ConstraintSystem<F>>(
-        &self,
-        cs: &mut CS,
-        z: &[AllocatedNum<F>],
-    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
-        let mut board = z.to_vec();
-
-        for i in 0..self.directions.len() {
-            let mut direction = Vec::new();
-            for (j, x) in self.directions[i].iter().enumerate() {
-                let var = AllocatedNum::alloc(
-                    cs.namespace(|| format!("alloc direction_{}_{}", i, j)),
-                    || Ok(*x),
-                )?;
-                direction.push(var)
-            }
-
-            let mut step_1 = DirectionChooser::new(&board, &direction);
-            step_1.synthesize(cs.namespace(|| "step_1"))?;
-
-            let mut step_2 = SortByZero::new(&step_1.lines, 0);
-            step_2.synthesize(cs.namespace(|| "step_2"))?;
-
-            let mut step_3 = Merge::new(&step_2.sorted_lines);
-            step_3.synthesize(cs.namespace(|| "step_3"))?;
-
-            let mut step_4 = SortByZero::new(&step_3.merged_lines, step_2.namespace_index);
-            step_4.synthesize(cs.namespace(|| "step_4"))?;
-
-            let mut step_5 = Restore::new(&step_4.sorted_lines, &direction);
-            step_5.synthesize(cs.namespace(|| "step_5"))?;
-
-            let step_6 = CanMove::new(&board, &step_5.board);
-            let moveable = step_6.synthesize(cs.namespace(|| "step_6"))?;
-
-            let mut step_7 = GenNext::new(&step_5.board, &moveable);
-            step_7.synthesize(cs.namespace(|| "step_7"))?;
-
-            board = step_7.new_board;
-
-            // println!("---------");
-            // use num_bigint::BigUint;
-            // for chunks in board.chunks(4) {
-            //     for x in chunks.iter() {
-            //         let val =
-            //             BigUint::from_bytes_le(x.get_value().unwrap_or(F::ZERO).to_repr().as_ref());
-            //         print!("{:?},", val);
-            //     }
-            //     println!();
-            // }
-            // println!("---------");
-        }
-
-        Ok(board)
-    }
-}
+const MOVES_PER_STEP: usize = 20;
 
 fn main() {
-    let directions = vec![
-        [UP; 20],
-        [DOWN; 20],
-        [LEFT; 20],
-        [RIGHT; 20],
-        [UP; 20],
-        [DOWN; 20],
-        [LEFT; 20],
-        [RIGHT; 20],
-        [UP; 20],
-        [DOWN; 20],
-    ];
-
-    let mut circuits_primary = vec![];
-    for chunks in directions.iter() {
-        circuits_primary.push(Game2048Circuit::new(chunks));
-    }
-
-    let circuit_secondary = TrivialCircuit::default();
-
-    let num_steps = directions.len();
-    println!("num steps:{num_steps}");
-
-    // produce public parameters
+    let moves = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+        Direction::Up,
+        Direction::Down,
+    ]
+    .into_iter()
+    .flat_map(|direction| [direction; MOVES_PER_STEP]);
+
+    // produce public parameters, or load them from an earlier run
     let start = Instant::now();
     println!("Producing public parameters...");
-    let pp = PublicParams::<
-        E1,
-        E2,
-        Game2048Circuit<<E1 as Group>::Scalar>,
-        TrivialCircuit<<E2 as Group>::Scalar>,
-    >::setup(
-        &circuits_primary[0],
-        &circuit_secondary,
-        &*default_ck_hint(),
-        &*default_ck_hint(),
-    );
+    let config = GameConfig::default();
+    let params_dir = std::env::temp_dir().join("nova-2048");
+    let prover = GameProver::<E1, E2>::load_or_setup(&params_dir, config, MOVES_PER_STEP).unwrap();
+    let pp = prover.public_params();
     println!("PublicParams::setup, took {:?} ", start.elapsed());
 
     println!(
@@ -159,47 +61,30 @@ fn main() {
         pp.num_variables().1
     );
 
-    let two = ONE + ONE;
-    let four = two.double();
     #[rustfmt::skip]
-        let z0_primary = vec![
-            ZERO, ZERO, two,  ZERO,
-            ZERO, four, ZERO, ZERO,
-            ZERO, ZERO, ZERO, ZERO,
-            ZERO, ZERO, ZERO, ZERO,
-        ];
-    let z0_secondary = vec![<E2 as Group>::Scalar::zero()];
+    let board = [
+        0, 0, 2, 0,
+        0, 4, 0, 0,
+        0, 0, 0, 0,
+        0, 0, 0, 0,
+    ];
+    let game =
+        Game::<<E1 as Group>::Scalar>::new(board, config).with_player(player_id(b"nova-2048"));
 
-    type C1 = Game2048Circuit<<E1 as Group>::Scalar>;
-    type C2 = TrivialCircuit<<E2 as Group>::Scalar>;
     // produce a recursive SNARK
     println!("Generating a RecursiveSNARK...");
-    let mut recursive_snark: RecursiveSNARK<E1, E2, C1, C2> =
-        RecursiveSNARK::<E1, E2, C1, C2>::new(
-            &pp,
-            &circuits_primary[0],
-            &circuit_secondary,
-            &z0_primary,
-            &z0_secondary,
-        )
-        .unwrap();
-
-    for (i, circuit_primary) in circuits_primary.iter().enumerate() {
-        let start = Instant::now();
-        let res = recursive_snark.prove_step(&pp, circuit_primary, &circuit_secondary);
-        assert!(res.is_ok());
-        println!(
-            "RecursiveSNARK::prove_step {}: {:?}, took {:?} ",
-            i,
-            res.is_ok(),
-            start.elapsed()
-        );
-    }
+    let start = Instant::now();
+    let proof = prover.prove(game, moves).unwrap();
+    println!(
+        "RecursiveSNARK of {} steps, took {:?} ",
+        proof.num_steps,
+        start.elapsed()
+    );
 
     // verify the recursive SNARK
     println!("Verifying a RecursiveSNARK...");
     let start = Instant::now();
-    let res = recursive_snark.verify(&pp, num_steps, &z0_primary, &z0_secondary);
+    let res = proof.verify(pp);
     println!(
         "RecursiveSNARK::verify: {:?}, took {:?}",
         res.is_ok(),
@@ -209,35 +94,30 @@ fn main() {
 
     // produce a compressed SNARK
     println!("Generating a CompressedSNARK using Spartan with IPA-PC...");
-    let (pk, vk) = CompressedSNARK::<_, _, _, _, S1, S2>::setup(&pp).unwrap();
+    let (pk, vk) = prover.compressed_keys::<S1, S2>().unwrap();
 
     let start = Instant::now();
-    type EE1 = nova_snark::provider::ipa_pc::EvaluationEngine<E1>;
-    type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
-    type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>;
-    type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>;
-
-    let res = CompressedSNARK::<_, _, _, _, S1, S2>::prove(&pp, &pk, &recursive_snark);
+    let res = prover.compress(&pk, &proof);
     println!(
         "CompressedSNARK::prove: {:?}, took {:?}",
         res.is_ok(),
         start.elapsed()
     );
     assert!(res.is_ok());
-    let compressed_snark = res.unwrap();
+    let compressed_proof = res.unwrap();
 
+    let bundle = ProofBundle::new(prover.params_key(), pp.digest(), compressed_proof);
+    let bytes = bundle.encode();
     let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
-    bincode::serialize_into(&mut encoder, &compressed_snark).unwrap();
-    let compressed_snark_encoded = encoder.finish().unwrap();
-    println!(
-        "CompressedSNARK::len {:?} bytes",
-        compressed_snark_encoded.len()
-    );
+    encoder.write_all(&bytes).unwrap();
+    let bundle_encoded = encoder.finish().unwrap();
+    println!("ProofBundle::len {:?} bytes", bundle_encoded.len());
 
     // verify the compressed SNARK
     println!("Verifying a CompressedSNARK...");
     let start = Instant::now();
-    let res = compressed_snark.verify(&vk, num_steps, &z0_primary, &z0_secondary);
+    let verifier = GameVerifier::new(vk, prover.params_key(), pp.digest());
+    let res = verify_game(&bytes, &verifier);
     println!(
         "CompressedSNARK::verify: {:?}, took {:?}",
         res.is_ok(),
@@ -0,0 +1,181 @@
+//! Proving a game in a background thread while it is played.
+//!
+//! `BackgroundProver` folds a step as soon as `moves_per_step` moves are
+//! played, so when the game ends only the last, partial step is left to fold
+//! before the proof can be compressed.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use nova_snark::traits::Group;
+
+use crate::{
+    game::{Direction, Game},
+    prover::{GameProof, GameProver, IncrementalProof, ProveError},
+};
+
+pub struct BackgroundProver<G1, G2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    prover: Arc<GameProver<G1, G2>>,
+
+    /// Sends the steps to the thread. Dropped to tell it the game is over.
+    steps: Sender<Vec<Direction>>,
+
+    /// The moves played since the last step was sent.
+    pending: Vec<Direction>,
+
+    num_steps: usize,
+
+    proven_steps: Arc<AtomicUsize>,
+
+    /// Stops at the first step which fails.
+    thread: JoinHandle<Result<IncrementalProof<G1, G2>, ProveError>>,
+}
+
+impl<G1, G2> BackgroundProver<G1, G2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar> + 'static,
+    G2: Group<Base = <G1 as Group>::Scalar> + 'static,
+{
+    /// Starts a thread proving the moves played from `game`, see `push`.
+    pub fn spawn(prover: Arc<GameProver<G1, G2>>, game: Game<G1::Scalar>) -> Self {
+        let (steps, receiver) = mpsc::channel::<Vec<Direction>>();
+        let proven_steps = Arc::new(AtomicUsize::new(0));
+
+        let thread = {
+            let prover = prover.clone();
+            let proven_steps = proven_steps.clone();
+            thread::spawn(move || {
+                let mut proof = prover.start(game)?;
+                for step in receiver {
+                    prover.prove_step(&mut proof, &step)?;
+                    proven_steps.fetch_add(1, Ordering::Relaxed);
+                }
+
+                Ok(proof)
+            })
+        };
+
+        Self {
+            prover,
+            steps,
+            pending: vec![],
+            num_steps: 0,
+            proven_steps,
+            thread,
+        }
+    }
+
+    /// Adds a move, and sends a step to the thread if it completes one.
+    pub fn push(&mut self, direction: Direction) {
+        self.pending.push(direction);
+        if self.pending.len() == self.prover.moves_per_step() {
+            self.send_step();
+        }
+    }
+
+    fn send_step(&mut self) {
+        let mut step = std::mem::take(&mut self.pending);
+        step.resize(self.prover.moves_per_step(), Direction::Noop);
+
+        // The thread only hangs up after a failure, which `finish` returns.
+        let _ = self.steps.send(step);
+        self.num_steps += 1;
+    }
+
+    /// The number of steps sent to the thread.
+    pub fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    /// The number of steps the thread has folded.
+    pub fn proven_steps(&self) -> usize {
+        self.proven_steps.load(Ordering::Relaxed)
+    }
+
+    /// Folds the pending moves, padded with `Direction::Noop`, waits for the
+    /// thread and returns the proof of all the moves.
+    pub fn finish(mut self) -> Result<GameProof<G1, G2>, ProveError> {
+        if !self.pending.is_empty() {
+            self.send_step();
+        }
+        drop(self.steps);
+
+        let proof = self
+            .thread
+            .join()
+            .map_err(|_| ProveError::ThreadPanicked)??;
+        self.prover.finish(proof)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use nova_snark::traits::Group;
+
+    use crate::{
+        circuit::GameConfig,
+        game::{Direction, Game},
+        prover::{GameProver, ProveError},
+    };
+
+    use super::BackgroundProver;
+
+    type E1 = pasta_curves::pallas::Point;
+    type E2 = pasta_curves::vesta::Point;
+    type F1 = <E1 as Group>::Scalar;
+
+    #[test]
+    fn test_background_prover() {
+        let config = GameConfig::default();
+        let prover = Arc::new(GameProver::<E1, E2>::setup(config, 2));
+        let game = Game::<F1>::new([2; 16], config);
+        let moves = [Direction::Left, Direction::Up, Direction::Right];
+
+        let mut background = BackgroundProver::spawn(prover.clone(), game.clone());
+        for direction in moves {
+            background.push(direction);
+        }
+        assert_eq!(background.num_steps(), 1);
+        assert!(background.proven_steps() <= 1);
+
+        let proof = background.finish().unwrap();
+        assert_eq!(proof.num_steps, 2);
+        assert_eq!(
+            proof.verify(prover.public_params()),
+            Ok(proof.zn_primary.clone())
+        );
+
+        let expected = prover.prove(game.clone(), moves).unwrap();
+        assert_eq!(proof.zn_primary, expected.zn_primary);
+
+        let background = BackgroundProver::spawn(prover.clone(), game);
+        assert!(matches!(background.finish(), Err(ProveError::NoMoves)));
+
+        // The error of the thread on a game of other rules.
+        let other = GameConfig {
+            check_tile_sum: !config.check_tile_sum,
+            ..config
+        };
+        let mut background = BackgroundProver::spawn(prover, Game::<F1>::new([2; 16], other));
+        background.push(Direction::Left);
+        assert_eq!(
+            background.finish().err(),
+            Some(ProveError::WrongConfig {
+                expected: config,
+                actual: other
+            })
+        );
+    }
+}
@@ -0,0 +1,304 @@
+//! A self-describing encoding of compressed proofs.
+//!
+//! A bundle starts with `BUNDLE_MAGIC`, then the bincode encoding of a
+//! `BundleHeader`, which holds everything a verifier needs besides the
+//! verifier key, then the bincode encoding of the compressed SNARK. The header
+//! names the curve cycle, and can be decoded without knowing it, see
+//! `decode_header`.
+
+use std::fmt;
+
+use ff::PrimeField;
+use nova_snark::traits::{snark::RelaxedR1CSSNARKTrait, Group};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    params::{element_bytes, element_from_bytes, ParamsKey},
+    prover::CompressedGameProof,
+};
+
+/// The first bytes of every bundle.
+pub const BUNDLE_MAGIC: [u8; 8] = *b"N2048PRF";
+
+/// The version of the format, bumped on every incompatible change.
+pub const BUNDLE_FORMAT_VERSION: u32 = 2;
+
+/// The curve cycle of a proof, as the moduli of the scalar fields of its
+/// primary and secondary curves, in the hexadecimal of `PrimeField::MODULUS`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurveCycle {
+    pub primary: String,
+
+    pub secondary: String,
+}
+
+impl CurveCycle {
+    pub fn new<G1, G2>() -> Self
+    where
+        G1: Group<Base = <G2 as Group>::Scalar>,
+        G2: Group<Base = <G1 as Group>::Scalar>,
+    {
+        Self {
+            primary: G1::Scalar::MODULUS.to_string(),
+            secondary: G2::Scalar::MODULUS.to_string(),
+        }
+    }
+}
+
+/// The header of a bundle. Field elements are encoded as their canonical
+/// representation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleHeader {
+    pub version: u32,
+
+    pub cycle: CurveCycle,
+
+    /// The digest of the circuit, its rules and its moves per step.
+    pub key: ParamsKey,
+
+    /// The digest of the public parameters of the circuit.
+    pub digest: Vec<u8>,
+
+    pub num_steps: usize,
+
+    pub z0_primary: Vec<Vec<u8>>,
+
+    /// The final state `z`, as the proof claims it.
+    pub zn_primary: Vec<Vec<u8>>,
+}
+
+/// The reason a bundle cannot be decoded.
+#[derive(Debug)]
+pub enum BundleError {
+    /// The bytes do not start with `BUNDLE_MAGIC`.
+    NotABundle,
+
+    /// The bundle has another format version.
+    WrongVersion(u32),
+
+    /// The bundle is for another curve cycle than expected.
+    WrongCycle(CurveCycle),
+
+    /// The bundle is for another circuit, rules or number of moves per
+    /// step than expected.
+    WrongKey(ParamsKey),
+
+    /// An element of the header is not a canonical field element.
+    InvalidElement,
+
+    /// The header or the proof is not a valid encoding.
+    Malformed(bincode::Error),
+
+    /// Bytes follow the proof.
+    TrailingBytes,
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::NotABundle => write!(f, "the bytes are not a proof bundle"),
+            BundleError::WrongVersion(version) => write!(
+                f,
+                "the bundle has format version {}, not {}",
+                version, BUNDLE_FORMAT_VERSION
+            ),
+            BundleError::WrongCycle(cycle) => write!(f, "the bundle is for {:?}", cycle),
+            BundleError::WrongKey(key) => write!(f, "the bundle is for {:?}", key),
+            BundleError::InvalidElement => write!(f, "the bundle holds an invalid field element"),
+            BundleError::Malformed(e) => write!(f, "the bundle is malformed: {}", e),
+            BundleError::TrailingBytes => write!(f, "bytes follow the proof of the bundle"),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+impl From<bincode::Error> for BundleError {
+    fn from(e: bincode::Error) -> Self {
+        BundleError::Malformed(e)
+    }
+}
+
+fn element<F: PrimeField>(bytes: &[u8]) -> Result<F, BundleError> {
+    element_from_bytes(bytes).ok_or(BundleError::InvalidElement)
+}
+
+fn elements<F: PrimeField>(elements: &[Vec<u8>]) -> Result<Vec<F>, BundleError> {
+    elements.iter().map(|x| element(x)).collect()
+}
+
+/// Decodes the header of a bundle, and returns it with the encoded proof
+/// which follows it.
+pub fn decode_header(bytes: &[u8]) -> Result<(BundleHeader, &[u8]), BundleError> {
+    let rest = bytes
+        .strip_prefix(BUNDLE_MAGIC.as_slice())
+        .ok_or(BundleError::NotABundle)?;
+
+    // The version comes first, so that a bundle of another version is told
+    // apart before its header is decoded.
+    let version: u32 = bincode::deserialize(rest)?;
+    if version != BUNDLE_FORMAT_VERSION {
+        return Err(BundleError::WrongVersion(version));
+    }
+
+    let mut reader = rest;
+    let header: BundleHeader = bincode::deserialize_from(&mut reader)?;
+
+    Ok((header, reader))
+}
+
+/// A compressed proof with what it was generated for.
+pub struct ProofBundle<G1, G2, S1, S2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    pub key: ParamsKey,
+
+    /// The digest of the public parameters of the circuit.
+    pub digest: G1::Scalar,
+
+    pub proof: CompressedGameProof<G1, G2, S1, S2>,
+}
+
+impl<G1, G2, S1, S2> ProofBundle<G1, G2, S1, S2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    pub fn new(
+        key: ParamsKey,
+        digest: G1::Scalar,
+        proof: CompressedGameProof<G1, G2, S1, S2>,
+    ) -> Self {
+        Self { key, digest, proof }
+    }
+
+    pub fn header(&self) -> BundleHeader {
+        let to_bytes = |z: &[G1::Scalar]| z.iter().map(|x| element_bytes(*x)).collect();
+
+        BundleHeader {
+            version: BUNDLE_FORMAT_VERSION,
+            cycle: CurveCycle::new::<G1, G2>(),
+            key: self.key.clone(),
+            digest: element_bytes(self.digest),
+            num_steps: self.proof.num_steps,
+            z0_primary: to_bytes(&self.proof.z0_primary),
+            zn_primary: to_bytes(&self.proof.zn_primary),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = BUNDLE_MAGIC.to_vec();
+        bincode::serialize_into(&mut bytes, &self.header()).unwrap();
+        bincode::serialize_into(&mut bytes, &self.proof.snark).unwrap();
+
+        bytes
+    }
+
+    /// Decodes a bundle, which must have been generated on the curve cycle of
+    /// `G1` and `G2` for `key`, and end with its proof.
+    pub fn decode(bytes: &[u8], key: &ParamsKey) -> Result<Self, BundleError> {
+        let (header, mut reader) = decode_header(bytes)?;
+        if header.cycle != CurveCycle::new::<G1, G2>() {
+            return Err(BundleError::WrongCycle(header.cycle));
+        }
+        if &header.key != key {
+            return Err(BundleError::WrongKey(header.key));
+        }
+
+        let snark = bincode::deserialize_from(&mut reader)?;
+        if !reader.is_empty() {
+            return Err(BundleError::TrailingBytes);
+        }
+
+        let proof = CompressedGameProof {
+            snark,
+            num_steps: header.num_steps,
+            z0_primary: elements(&header.z0_primary)?,
+            zn_primary: elements(&header.zn_primary)?,
+        };
+
+        Ok(Self {
+            key: header.key,
+            digest: element(&header.digest)?,
+            proof,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        circuit::GameConfig,
+        game::{Direction, Game},
+        params::ParamsKey,
+        prover::GameProver,
+    };
+
+    use super::{
+        decode_header, BundleError, CurveCycle, ProofBundle, BUNDLE_FORMAT_VERSION, BUNDLE_MAGIC,
+    };
+
+    type E1 = pasta_curves::pallas::Point;
+    type E2 = pasta_curves::vesta::Point;
+    type EE1 = nova_snark::provider::ipa_pc::EvaluationEngine<E1>;
+    type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
+    type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>;
+    type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>;
+
+    #[test]
+    fn test_bundle() {
+        let config = GameConfig::default();
+        let prover = GameProver::<E1, E2>::setup(config, 1);
+        let game = Game::new([2; 16], config);
+        let proof = prover.prove(game, [Direction::Left]).unwrap();
+        let (pk, vk) = prover.compressed_keys::<S1, S2>().unwrap();
+        let proof = prover.compress(&pk, &proof).unwrap();
+        let zn_primary = proof.zn_primary.clone();
+
+        let digest = prover.public_params().digest();
+        let bundle = ProofBundle::new(prover.params_key(), digest, proof);
+        let bytes = bundle.encode();
+
+        let (header, _) = decode_header(&bytes).unwrap();
+        assert_eq!(header, bundle.header());
+        assert_eq!(header.num_steps, 1);
+        assert_eq!(header.cycle, CurveCycle::new::<E1, E2>());
+
+        let decoded = ProofBundle::<E1, E2, S1, S2>::decode(&bytes, &prover.params_key()).unwrap();
+        assert_eq!(decoded.digest, digest);
+        assert_eq!(decoded.proof.zn_primary, zn_primary);
+        assert_eq!(decoded.proof.verify(&vk), Ok(zn_primary));
+
+        let other = ParamsKey::new::<E1>(config, 2);
+        let res = ProofBundle::<E1, E2, S1, S2>::decode(&bytes, &other);
+        assert!(matches!(res, Err(BundleError::WrongKey(_))));
+
+        // The cycle is told apart even with the curves swapped.
+        let res = ProofBundle::<E2, E1, S2, S1>::decode(&bytes, &prover.params_key());
+        assert!(matches!(res, Err(BundleError::WrongCycle(c)) if c == header.cycle));
+
+        let res = ProofBundle::<E1, E2, S1, S2>::decode(&bytes[1..], &prover.params_key());
+        assert!(matches!(res, Err(BundleError::NotABundle)));
+
+        let mut newer = bytes.clone();
+        newer[BUNDLE_MAGIC.len()..BUNDLE_MAGIC.len() + 4]
+            .copy_from_slice(&(BUNDLE_FORMAT_VERSION + 1).to_le_bytes());
+        let res = ProofBundle::<E1, E2, S1, S2>::decode(&newer, &prover.params_key());
+        assert!(matches!(res, Err(BundleError::WrongVersion(_))));
+
+        let res =
+            ProofBundle::<E1, E2, S1, S2>::decode(&bytes[..bytes.len() - 10], &prover.params_key());
+        assert!(matches!(res, Err(BundleError::Malformed(_))));
+
+        let mut longer = bytes.clone();
+        longer.push(0);
+        let res = ProofBundle::<E1, E2, S1, S2>::decode(&longer, &prover.params_key());
+        assert!(matches!(res, Err(BundleError::TrailingBytes)));
+    }
+}
@@ -0,0 +1,321 @@
+//! Checkpoints of proofs in progress, so that proving a long game survives a
+//! crash.
+//!
+//! A checkpoint holds a `CheckpointHeader`, then the bincode encoding of the
+//! recursive SNARK, the state of the game before the first step, the moves of
+//! the folded steps and the moves left to prove. Loading a checkpoint checks
+//! it against the prover: the key and the digest of its public parameters,
+//! then the SNARK itself, see `load_checkpoint`.
+//!
+//! A checkpoint knows the private parts of the state, so it stays with the
+//! prover.
+
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use ff::Field;
+use nova_snark::traits::Group;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    game::{Direction, Game},
+    params::{element_bytes, ParamsKey},
+    prover::{GameProver, GameRecursiveSNARK, IncrementalProof, ProveError},
+};
+
+/// The version of the file format, bumped on every incompatible change.
+pub const CHECKPOINT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointHeader {
+    pub version: u32,
+
+    /// The digest of the circuit, its rules and its moves per step.
+    pub key: ParamsKey,
+
+    /// The digest of the public parameters of the circuit.
+    pub digest: Vec<u8>,
+
+    /// The number of folded steps.
+    pub num_steps: usize,
+
+    /// The Keccak-256 hash of the rest of the file. It is not keyed, so it
+    /// only catches corruption: the SNARK is still checked on load.
+    pub checksum: Vec<u8>,
+}
+
+/// The reason a checkpoint cannot be saved or loaded.
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+
+    /// The file is not a valid encoding.
+    Malformed(bincode::Error),
+
+    /// The file has another format version.
+    WrongVersion(u32),
+
+    /// The checkpoint is for another circuit, config or number of moves
+    /// per step than the prover.
+    WrongKey(ParamsKey),
+
+    /// The checkpoint is for other public parameters than the prover's.
+    WrongDigest,
+
+    /// The file does not match the checksum of its header: it is corrupted.
+    WrongChecksum,
+
+    /// The SNARK does not prove the moves of the checkpoint with the public
+    /// parameters of the prover.
+    WrongProof,
+
+    /// The state the proof starts from is not the state of a game, see
+    /// `Game::try_from_z`.
+    WrongStart,
+
+    /// The prover cannot resume the proof, see `ProveError`.
+    Prove(ProveError),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "cannot access the checkpoint: {}", e),
+            CheckpointError::Malformed(e) => write!(f, "the checkpoint is malformed: {}", e),
+            CheckpointError::WrongVersion(version) => write!(
+                f,
+                "the checkpoint has format version {}, not {}",
+                version, CHECKPOINT_FORMAT_VERSION
+            ),
+            CheckpointError::WrongKey(key) => write!(f, "the checkpoint is for {:?}", key),
+            CheckpointError::WrongDigest => {
+                write!(f, "the checkpoint is for other public parameters")
+            }
+            CheckpointError::WrongChecksum => write!(f, "the checkpoint is corrupted"),
+            CheckpointError::WrongProof => {
+                write!(f, "the checkpoint does not hold a valid proof of its moves")
+            }
+            CheckpointError::WrongStart => {
+                write!(f, "the checkpoint does not start from the state of a game")
+            }
+            CheckpointError::Prove(e) => write!(f, "cannot resume the proof: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<io::Error> for CheckpointError {
+    fn from(e: io::Error) -> Self {
+        CheckpointError::Io(e)
+    }
+}
+
+impl From<ProveError> for CheckpointError {
+    fn from(e: ProveError) -> Self {
+        CheckpointError::Prove(e)
+    }
+}
+
+impl From<bincode::Error> for CheckpointError {
+    fn from(e: bincode::Error) -> Self {
+        CheckpointError::Malformed(e)
+    }
+}
+
+/// Saves `proof` with the moves left to prove.
+///
+/// The game is resumed from its state before the first step, so with a board
+/// history the proof must start before the first move, see `Game::from_state`.
+pub fn save_checkpoint<G1, G2>(
+    path: &Path,
+    prover: &GameProver<G1, G2>,
+    proof: &IncrementalProof<G1, G2>,
+    remaining: &[Direction],
+) -> Result<(), CheckpointError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    let start = proof.start.state().to_z();
+    let encoded = bincode::serialize(&(&proof.snark, &start, &proof.moves, remaining))?;
+    let header = CheckpointHeader {
+        version: CHECKPOINT_FORMAT_VERSION,
+        key: prover.params_key(),
+        digest: element_bytes(prover.public_params().digest()),
+        num_steps: proof.num_steps,
+        checksum: Keccak256::digest(&encoded).to_vec(),
+    };
+
+    // Written next to the checkpoint first, so that a crash while saving
+    // leaves the previous checkpoint untouched.
+    let tmp = path.with_extension("tmp");
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    bincode::serialize_into(&mut writer, &header)?;
+    writer.write_all(&encoded)?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+/// Loads a checkpoint saved by `save_checkpoint`, and returns the proof with
+/// the moves left to prove, to be folded with `GameProver::prove_step`.
+///
+/// The checkpoint must have been saved by a prover with the same key and
+/// public parameters, and its SNARK must verify against them with the state
+/// of its moves.
+pub fn load_checkpoint<G1, G2>(
+    path: &Path,
+    prover: &GameProver<G1, G2>,
+) -> Result<(IncrementalProof<G1, G2>, Vec<Direction>), CheckpointError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    let mut reader = BufReader::new(File::open(path)?);
+    let header: CheckpointHeader = bincode::deserialize_from(&mut reader)?;
+    if header.version != CHECKPOINT_FORMAT_VERSION {
+        return Err(CheckpointError::WrongVersion(header.version));
+    }
+    if header.key != prover.params_key() {
+        return Err(CheckpointError::WrongKey(header.key));
+    }
+    if header.digest != element_bytes(prover.public_params().digest()) {
+        return Err(CheckpointError::WrongDigest);
+    }
+
+    let mut encoded = Vec::new();
+    reader.read_to_end(&mut encoded)?;
+    if Keccak256::digest(&encoded)[..] != header.checksum[..] {
+        return Err(CheckpointError::WrongChecksum);
+    }
+
+    #[allow(clippy::type_complexity)]
+    let (snark, start, moves, remaining): (
+        Option<GameRecursiveSNARK<G1, G2>>,
+        Vec<G1::Scalar>,
+        Vec<Direction>,
+        Vec<Direction>,
+    ) = bincode::deserialize(&encoded)?;
+
+    if header.num_steps.checked_mul(prover.moves_per_step()) != Some(moves.len()) {
+        return Err(CheckpointError::WrongProof);
+    }
+    let start = Game::try_from_z(&start, prover.config()).ok_or(CheckpointError::WrongStart)?;
+    let mut proof = prover.start(start)?;
+    for direction in &moves {
+        proof.game.play(*direction);
+    }
+    proof.moves = moves;
+    proof.num_steps = header.num_steps;
+
+    // The SNARK is checked against the states replayed from its moves.
+    match &snark {
+        Some(snark) => {
+            let (zn_primary, _) = snark
+                .verify(
+                    prover.public_params(),
+                    proof.num_steps,
+                    &proof.z0_primary,
+                    &[G2::Scalar::ZERO],
+                )
+                .map_err(|_| CheckpointError::WrongProof)?;
+            if zn_primary != prover.z(&proof.game, proof.num_steps)? {
+                return Err(CheckpointError::WrongProof);
+            }
+        }
+        None if proof.num_steps == 0 => {}
+        None => return Err(CheckpointError::WrongProof),
+    }
+    proof.snark = snark;
+
+    Ok((proof, remaining))
+}
+
+#[cfg(test)]
+mod test {
+    use nova_snark::traits::Group;
+
+    use crate::{
+        circuit::GameConfig,
+        game::{Direction, Game},
+        prover::GameProver,
+    };
+
+    use super::{load_checkpoint, save_checkpoint, CheckpointError};
+
+    type E1 = pasta_curves::pallas::Point;
+    type E2 = pasta_curves::vesta::Point;
+    type F1 = <E1 as Group>::Scalar;
+
+    #[test]
+    fn test_checkpoint() {
+        let dir = std::env::temp_dir().join(format!("nova-2048-checkpoint-{}", std::process::id()));
+        let path = dir.join("game.ckpt");
+        let config = GameConfig::default();
+        let prover = GameProver::<E1, E2>::setup(config, 1);
+        let game = Game::<F1>::new([2; 16], config);
+        let moves = [Direction::Left, Direction::Up, Direction::Right];
+
+        let mut proof = prover.start(game.clone()).unwrap();
+        prover.prove_step(&mut proof, &moves[..1]).unwrap();
+        save_checkpoint(&path, &prover, &proof, &moves[1..]).unwrap();
+
+        let (mut resumed, remaining) = load_checkpoint(&path, &prover).unwrap();
+        assert_eq!(resumed.num_steps(), 1);
+        assert_eq!(resumed.game().state(), proof.game().state());
+        assert_eq!(remaining, moves[1..]);
+        for direction in remaining {
+            prover.prove_step(&mut resumed, &[direction]).unwrap();
+        }
+        let resumed = prover.finish(resumed).unwrap();
+        let expected = prover.prove(game.clone(), moves).unwrap();
+        assert_eq!(resumed.num_steps, 3);
+        assert_eq!(resumed.zn_primary, expected.zn_primary);
+        assert_eq!(
+            resumed.verify(prover.public_params()),
+            Ok(expected.zn_primary)
+        );
+
+        // Another number of moves per step.
+        let other = GameProver::<E1, E2>::setup(config, 2);
+        let res = load_checkpoint(&path, &other);
+        assert!(matches!(res, Err(CheckpointError::WrongKey(_))));
+
+        // A SNARK which does not prove the moves.
+        let mut forged = prover.start(game.clone()).unwrap();
+        prover.prove_step(&mut forged, &moves[..1]).unwrap();
+        forged.moves = vec![Direction::Down];
+        save_checkpoint(&path, &prover, &forged, &[]).unwrap();
+        let res = load_checkpoint(&path, &prover);
+        assert!(matches!(res, Err(CheckpointError::WrongProof)));
+
+        // A start which is not the state of a game, with a matching checksum.
+        let mut forged = prover.start(game.clone()).unwrap();
+        forged.start.board[0] = 3;
+        save_checkpoint(&path, &prover, &forged, &[]).unwrap();
+        let res = load_checkpoint(&path, &prover);
+        assert!(matches!(res, Err(CheckpointError::WrongStart)));
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        std::fs::write(&path, bytes).unwrap();
+        let res = load_checkpoint(&path, &prover);
+        assert!(matches!(res, Err(CheckpointError::WrongChecksum)));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
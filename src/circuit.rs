@@ -0,0 +1,850 @@
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeFieldBits;
+use nova_snark::traits::circuit::StepCircuit;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    can_move::CanMove,
+    claim::Claim,
+    commitment::{commit_state_circuit, PrivateInput},
+    direction_chooser::DirectionChooser,
+    game_over::{select_state, GameOver},
+    gen_next::GenNext,
+    history::{AppendBoard, HISTORY_DEPTH},
+    max_tile::{MaxTile, ReachedTarget},
+    merge::Merge,
+    merge_counter::MergeCounter,
+    packing::{pack_circuit, unpack_circuit},
+    poseidon::hash_circuit,
+    restore::Restore,
+    sort::SortByZero,
+    state::GameState,
+    tile_sum::TileSum,
+    transcript::{direction_code, Transcript},
+    NumConstraintSystem,
+};
+
+/// Options of `Game2048Circuit`. Different options produce different
+/// circuits, so the same options must be used at setup and proving time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameConfig {
+    /// Enforce that every move preserves the sum of the tiles plus the
+    /// spawned number, see `TileSum`. A check against bugs in the gadgets of
+    /// a move, off by default as it only costs constraints in production.
+    pub check_tile_sum: bool,
+
+    /// Absorb the index and the value of every spawned number into the
+    /// transcript, besides the directions.
+    pub transcript_spawns: bool,
+
+    /// Count the moves which do not change the board in `GameState::moves`.
+    /// Otherwise, only the moves which `CanMove` are counted.
+    pub count_stuck_moves: bool,
+
+    /// The tile which sets `GameState::reached`.
+    pub target: u64,
+
+    /// Keep the board and the state private: `z` carries a hiding commitment
+    /// to the state (see `commit_state`), and only reveals the player, the
+    /// nonce, the score and the max tile. Every step then needs a
+    /// `PrivateInput`. The circuit takes the state committed in `z0` as it is,
+    /// so the verifier checks it with its opening, see `StartOpening`.
+    pub private_board: bool,
+
+    /// Pack the board into one element of `z` (16 exponents of 5 bits, see
+    /// `pack`), which shrinks `z` by 15 elements at the cost of unpacking and
+    /// repacking it in every step. The smaller `z` is cheaper to hash in the
+    /// recursive verifier: with Nova over pallas/vesta and the default config,
+    /// `pp.num_constraints()` of the primary circuit goes from 18772 to 16115
+    /// with 1 move per step, and from 42982 to 40325 with 10. Not used in
+    /// private-board mode, where the board is committed anyway.
+    pub packed_board: bool,
+
+    /// On top of private-board mode, commit to the whole state but the player
+    /// and the nonce (see `commit_state`), and only reveal whether a claim
+    /// holds: `z` carries the thresholds of the claim, set in `z0`, and a bit
+    /// which is 1 if score >= score threshold and max tile >= max tile
+    /// threshold, see `Claim`. As in private-board mode, the verifier checks
+    /// the state committed in `z0` with its opening, see `StartOpening`.
+    pub claim: bool,
+
+    /// Append the board after every counted move (see `count_stuck_moves`)
+    /// to a Merkle tree, whose root `z` carries, so that the board at any move
+    /// can be opened later, see `BoardHistory`. `z0` must then hold the root of
+    /// the empty tree, and every step needs the paths of its appends. The tree
+    /// holds 2^HISTORY_DEPTH boards, so a game cannot count more moves, see
+    /// `ProveError::HistoryFull`.
+    pub board_history: bool,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            check_tile_sum: false,
+            transcript_spawns: false,
+            count_stuck_moves: true,
+            target: 2048,
+            private_board: false,
+            packed_board: false,
+            claim: false,
+            board_history: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Game2048Circuit<F: PrimeFieldBits> {
+    pub directions: Vec<[F; 4]>,
+
+    pub config: GameConfig,
+
+    /// The private inputs of the step in private-board and claim modes. Zeros
+    /// are used if it is missing, which is enough for the setup.
+    pub private_input: Option<PrivateInput<F>>,
+
+    /// The paths of the appends to the board history, one per move, see
+    /// `BoardHistory::next_path`. Zeros are used if they are missing.
+    pub history_paths: Option<Vec<Vec<F>>>,
+}
+
+impl<F: PrimeFieldBits> Game2048Circuit<F> {
+    pub fn new(directions: &[[F; 4]]) -> Self {
+        Self::with_config(directions, GameConfig::default())
+    }
+
+    pub fn with_config(directions: &[[F; 4]], config: GameConfig) -> Self {
+        assert!(
+            !config.claim || config.private_board,
+            "claim mode needs private-board mode"
+        );
+
+        Self {
+            directions: directions.to_vec(),
+            config,
+            private_input: None,
+            history_paths: None,
+        }
+    }
+
+    /// Returns a circuit of `moves_per_step` moves, which has the shape of any
+    /// circuit of that many moves with `config`. Only fit for the setup.
+    pub fn blank(moves_per_step: usize, config: GameConfig) -> Self {
+        assert!(moves_per_step > 0, "a step needs at least one move");
+
+        let directions = vec![[F::ONE, F::ZERO, F::ZERO, F::ZERO]; moves_per_step];
+        Self::with_config(&directions, config)
+    }
+
+    /// The number of moves of the step, which the public parameters are
+    /// generated for.
+    pub fn moves_per_step(&self) -> usize {
+        self.directions.len()
+    }
+
+    /// Sets the paths of the appends to the board history, see
+    /// `GameConfig::board_history`.
+    pub fn with_history_paths(mut self, history_paths: Vec<Vec<F>>) -> Self {
+        assert_eq!(history_paths.len(), self.directions.len());
+
+        self.history_paths = Some(history_paths);
+        self
+    }
+
+    /// Sets the private inputs of the step, see `GameConfig::private_board`.
+    pub fn with_private_input(mut self, private_input: PrivateInput<F>) -> Self {
+        self.private_input = Some(private_input);
+        self
+    }
+
+    /// Allocates the opening of the commitment in `z`, enforces it, and
+    /// returns the full state.
+    fn open<CS: ConstraintSystem<F>>(
+        &self,
+        mut cs: CS,
+        z: &[AllocatedNum<F>],
+    ) -> Result<(GameState<AllocatedNum<F>>, AllocatedNum<F>), SynthesisError> {
+        let input = self.private_input.clone().unwrap_or_default();
+
+        let mut opening = Vec::new();
+        for (i, x) in input.state.to_z().iter().enumerate() {
+            opening.push(AllocatedNum::alloc(
+                cs.namespace(|| format!("alloc_opening_{}", i)),
+                || Ok(*x),
+            )?);
+        }
+        let blinding =
+            AllocatedNum::alloc(cs.namespace(|| "alloc_blinding"), || Ok(input.blinding))?;
+        let next_blinding = AllocatedNum::alloc(cs.namespace(|| "alloc_next_blinding"), || {
+            Ok(input.next_blinding)
+        })?;
+
+        // The elements that `z` reveals are taken from it.
+        let mut state = GameState::from_z(&opening);
+        state.player = z[1].clone();
+        state.nonce = z[2].clone();
+        if !self.config.claim {
+            state.score = z[3].clone();
+            state.max_tile = z[4].clone();
+        }
+
+        let commitment = commit_state_circuit(cs.namespace(|| "commit"), &state, &blinding)?;
+        cs.enforce(
+            || "enforce_(commitment = z[0])",
+            |lc| lc + commitment.get_variable(),
+            |lc| lc + CS::one(),
+            |lc| lc + z[0].get_variable(),
+        );
+
+        Ok((state, next_blinding))
+    }
+}
+
+impl<F: PrimeFieldBits> StepCircuit<F> for Game2048Circuit<F> {
+    fn arity(&self) -> usize {
+        if self.config.claim {
+            GameState::<F>::CLAIM_ARITY
+        } else if self.config.private_board {
+            GameState::<F>::PRIVATE_ARITY
+        } else if self.config.packed_board {
+            GameState::<F>::PACKED_ARITY
+        } else {
+            GameState::<F>::ARITY
+        }
+    }
+
+    fn synthesize<CS: ConstraintSystem<F>>(
+        &self,
+        cs: &mut CS,
+        z: &[AllocatedNum<F>],
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+        let (mut state, next_blinding) = if self.config.private_board {
+            let (state, next_blinding) = self.open(cs.namespace(|| "open"), z)?;
+            (state, Some(next_blinding))
+        } else if self.config.packed_board {
+            let board = unpack_circuit(cs.namespace(|| "unpack"), &z[0])?;
+            (GameState::from_packed_z(z, &board), None)
+        } else {
+            (GameState::from_z(z), None)
+        };
+
+        for i in 0..self.directions.len() {
+            let mut cs = cs.namespace(|| format!("move_{}", i));
+            let previous = state.clone();
+
+            let mut direction = Vec::new();
+            for (j, x) in self.directions[i].iter().enumerate() {
+                let var = AllocatedNum::alloc(
+                    cs.namespace(|| format!("alloc direction_{}_{}", i, j)),
+                    || Ok(*x),
+                )?;
+                direction.push(var)
+            }
+
+            let mut step_1 = DirectionChooser::new(&state.board, &direction);
+            step_1.synthesize(cs.namespace(|| "step_1"))?;
+
+            let mut step_2 = SortByZero::new(&step_1.lines, 0);
+            step_2.synthesize(cs.namespace(|| "step_2"))?;
+
+            let mut step_3 = Merge::new(&step_2.sorted_lines);
+            step_3.synthesize(cs.namespace(|| "step_3"))?;
+
+            let merged_sum =
+                AllocatedNum::sum(cs.namespace(|| "sum_of_merged"), &step_3.merged_numbers)?;
+            state.score = state.score.add(cs.namespace(|| "add_score"), &merged_sum)?;
+
+            let mut step_4 = SortByZero::new(&step_3.merged_lines, step_2.namespace_index);
+            step_4.synthesize(cs.namespace(|| "step_4"))?;
+
+            let mut step_5 = Restore::new(&step_4.sorted_lines, &direction);
+            step_5.synthesize(cs.namespace(|| "step_5"))?;
+
+            let step_6 = CanMove::new(&state.board, &step_5.board);
+            let moveable = step_6.synthesize(cs.namespace(|| "step_6"))?;
+
+            let counted = if self.config.count_stuck_moves {
+                let one = AllocatedNum::alloc(cs.namespace(|| "alloc_one"), || Ok(F::ONE))?;
+                cs.enforce(
+                    || "enforce_one",
+                    |lc| lc + one.get_variable(),
+                    |lc| lc + CS::one(),
+                    |lc| lc + CS::one(),
+                );
+                one
+            } else {
+                moveable.clone()
+            };
+            let moves = state.moves.clone();
+            state.moves = moves.add(cs.namespace(|| "count_move"), &counted)?;
+
+            // The spawned number is picked by seed = hash(player, nonce, transcript, code).
+            let code = direction_code(cs.namespace(|| "direction_code"), &direction)?;
+            let seed = hash_circuit(
+                cs.namespace(|| "seed"),
+                &[
+                    state.player.clone(),
+                    state.nonce.clone(),
+                    state.transcript.clone(),
+                    code.clone(),
+                ],
+            )?;
+
+            let mut step_7 = GenNext::with_seed(&step_5.board, &moveable, &seed);
+            step_7.synthesize(cs.namespace(|| "step_7"))?;
+
+            if self.config.check_tile_sum {
+                let new_number = step_7.new_number.as_ref().unwrap();
+                // The lines hold the tiles of the board, or none for a no-op.
+                let old_board: Vec<_> = step_1.lines.concat();
+                let step_8 = TileSum::new(&old_board, &step_7.new_board, new_number);
+                step_8.synthesize(cs.namespace(|| "step_8"))?;
+            }
+
+            let spawn = if self.config.transcript_spawns {
+                Some((
+                    step_7.new_index.as_ref().unwrap(),
+                    step_7.new_number.as_ref().unwrap(),
+                ))
+            } else {
+                None
+            };
+            let step_9 = Transcript::new(&state.transcript, &code, spawn);
+            state.transcript = step_9.synthesize(cs.namespace(|| "step_9"))?;
+
+            let step_10 = MaxTile::new(&state.max_tile, &step_7.new_board);
+            state.max_tile = step_10.synthesize(cs.namespace(|| "step_10"))?;
+
+            let step_11 = ReachedTarget::new(
+                &state.max_tile,
+                self.config.target,
+                &state.reached,
+                &state.reached_at,
+                &state.moves,
+            );
+            (state.reached, state.reached_at) = step_11.synthesize(cs.namespace(|| "step_11"))?;
+
+            state.board = step_7.new_board;
+
+            let step_12 = MergeCounter::new(&state.merges, &step_3.merged_numbers);
+            state.merges = step_12.synthesize(cs.namespace(|| "step_12"))?;
+
+            // Once the game is over, every move leaves the state untouched.
+            let step_13 = GameOver::new(&previous, &state);
+            state = step_13.synthesize(cs.namespace(|| "step_13"))?;
+
+            // A no-op leaves the state untouched too.
+            let is_noop = step_1.is_noop.as_ref().unwrap();
+            state = select_state(cs.namespace(|| "noop"), is_noop, &previous, &state)?;
+
+            // The board is appended if the move is still counted, so neither
+            // once the game is over nor for a no-op.
+            if self.config.board_history {
+                let path = match &self.history_paths {
+                    Some(paths) => paths[i].clone(),
+                    None => vec![F::ZERO; HISTORY_DEPTH],
+                };
+                let appended = state
+                    .moves
+                    .sub(cs.namespace(|| "appended"), &previous.moves)?;
+                let step_14 = AppendBoard::new(
+                    &state.history,
+                    &previous.moves,
+                    &appended,
+                    &state.board,
+                    &path,
+                );
+                state.history = step_14.synthesize(cs.namespace(|| "step_14"))?;
+            }
+        }
+
+        match next_blinding {
+            Some(blinding) if self.config.claim => {
+                let commitment =
+                    commit_state_circuit(cs.namespace(|| "commit"), &state, &blinding)?;
+
+                let (score_threshold, max_tile_threshold) = (&z[3], &z[4]);
+                let claim = Claim::new(
+                    &state.score,
+                    &state.max_tile,
+                    score_threshold,
+                    max_tile_threshold,
+                );
+                let holds = claim.synthesize(cs.namespace(|| "claim"))?;
+
+                Ok(state.to_claim_z(
+                    commitment,
+                    score_threshold.clone(),
+                    max_tile_threshold.clone(),
+                    holds,
+                ))
+            }
+            Some(blinding) => {
+                let commitment =
+                    commit_state_circuit(cs.namespace(|| "commit"), &state, &blinding)?;
+                Ok(state.to_private_z(commitment))
+            }
+            None if self.config.packed_board => {
+                let packed = pack_circuit(cs.namespace(|| "pack"), &state.board)?;
+                Ok(state.to_packed_z(packed))
+            }
+            None => Ok(state.to_z()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+    use blstrs::Scalar as Fr;
+    use nova_snark::traits::circuit::StepCircuit;
+
+    use crate::{
+        game::{player_id, Direction, Game},
+        prover::GameProver,
+    };
+
+    use super::{Game2048Circuit, GameConfig};
+
+    type E1 = pasta_curves::pallas::Point;
+    type E2 = pasta_curves::vesta::Point;
+
+    #[rustfmt::skip]
+    const BOARD: [u64; 16] = [
+        0, 0, 2, 0,
+        0, 4, 0, 0,
+        0, 0, 0, 0,
+        0, 0, 0, 0,
+    ];
+
+    /// Synthesizes one step from `game` and checks it against `Game::play`.
+    /// Returns the number of constraints.
+    fn check_step(game: &mut Game<Fr>, directions: &[Direction]) -> usize {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+
+        let z_of = |game: &Game<Fr>| {
+            if game.config.packed_board {
+                game.packed_z()
+            } else {
+                game.state().to_z()
+            }
+        };
+
+        let mut z = Vec::new();
+        for (i, x) in z_of(game).iter().enumerate() {
+            z.push(AllocatedNum::alloc(cs.namespace(|| format!("z_{i}")), || Ok(*x)).unwrap());
+        }
+
+        let bits: Vec<_> = directions.iter().map(|d| d.to_bits()).collect();
+        let mut circuit = Game2048Circuit::with_config(&bits, game.config);
+        if game.config.board_history {
+            circuit = circuit.with_history_paths(game.history_paths(directions).unwrap());
+        }
+        assert_eq!(circuit.arity(), z.len());
+        let output = circuit.synthesize(&mut cs, &z).unwrap();
+        assert!(cs.is_satisfied());
+
+        for direction in directions {
+            game.play(*direction);
+        }
+        let output: Vec<_> = output.iter().map(|x| x.get_value().unwrap()).collect();
+        assert_eq!(output, z_of(game));
+
+        cs.num_constraints()
+    }
+
+    #[test]
+    fn test_blank() {
+        let config = GameConfig::default();
+        let game = Game::<Fr>::new(BOARD, config);
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let mut z = Vec::new();
+        for (i, x) in game.state().to_z().iter().enumerate() {
+            z.push(AllocatedNum::alloc(cs.namespace(|| format!("z_{i}")), || Ok(*x)).unwrap());
+        }
+        let circuit = Game2048Circuit::blank(3, config);
+        assert_eq!(circuit.moves_per_step(), 3);
+        circuit.synthesize(&mut cs, &z).unwrap();
+
+        let num_constraints = check_step(
+            &mut game.clone(),
+            &[Direction::Left, Direction::Down, Direction::Right],
+        );
+        assert_eq!(cs.num_constraints(), num_constraints);
+    }
+
+    #[test]
+    fn test_check_tile_sum() {
+        let mut num_constraints = vec![];
+        for check_tile_sum in [true, false] {
+            let config = GameConfig {
+                check_tile_sum,
+                ..Default::default()
+            };
+            let mut game = Game::<Fr>::new(BOARD, config);
+            num_constraints.push(check_step(&mut game, &[Direction::Up, Direction::Left]));
+        }
+
+        assert!(num_constraints[0] > num_constraints[1]);
+    }
+
+    #[test]
+    fn test_transcript() {
+        let moves = [
+            Direction::Up,
+            Direction::Left,
+            Direction::Left,
+            Direction::Down,
+            Direction::Right,
+        ];
+
+        let mut transcripts = vec![];
+        for transcript_spawns in [false, true] {
+            let config = GameConfig {
+                transcript_spawns,
+                ..Default::default()
+            };
+            let mut game = Game::<Fr>::new(BOARD, config);
+            check_step(&mut game, &moves[..2]);
+            check_step(&mut game, &moves[2..]);
+
+            // The transcript only depends on the moves, so it can be replayed.
+            let mut replayed = Game::<Fr>::new(BOARD, config);
+            for direction in moves {
+                replayed.play(direction);
+            }
+            assert_eq!(replayed.transcript, game.transcript);
+
+            transcripts.push(game.transcript);
+        }
+
+        assert_ne!(transcripts[0], transcripts[1]);
+    }
+
+    #[test]
+    fn test_player() {
+        let moves = [Direction::Up, Direction::Left, Direction::Down];
+
+        let mut boards = vec![];
+        for account in ["alice", "bob"] {
+            let player = player_id(account.as_bytes());
+            let mut game = Game::<Fr>::new(BOARD, GameConfig::default()).with_player(player);
+            check_step(&mut game, &moves);
+
+            assert_eq!(game.state().player, player);
+            boards.push(game.board);
+        }
+
+        // The same moves spawn different numbers for different players.
+        assert_ne!(boards[0], boards[1]);
+    }
+
+    #[test]
+    fn test_moves() {
+        #[rustfmt::skip]
+        let board = [
+            2, 4, 2, 4,
+            4, 2, 4, 2,
+            2, 4, 2, 4,
+            0, 0, 0, 0,
+        ];
+        let directions = [Direction::Up, Direction::Left, Direction::Down];
+
+        let mut game = Game::<Fr>::new(board, GameConfig::default());
+        check_step(&mut game, &directions);
+        assert_eq!(game.moves, 3);
+
+        // Sliding up packs nothing as the top rows are full, so only moving
+        // down changes the board.
+        let config = GameConfig {
+            count_stuck_moves: false,
+            ..GameConfig::default()
+        };
+        let mut game = Game::<Fr>::new(board, config);
+        check_step(&mut game, &directions);
+        assert_eq!(game.moves, 1);
+    }
+
+    #[test]
+    fn test_noop() {
+        for config in [
+            GameConfig::default(),
+            GameConfig {
+                transcript_spawns: true,
+                board_history: true,
+                ..GameConfig::default()
+            },
+        ] {
+            let mut game = Game::<Fr>::new(BOARD, config);
+            check_step(&mut game, &[Direction::Left, Direction::Noop]);
+            let state = game.state();
+
+            check_step(&mut game, &[Direction::Noop, Direction::Noop]);
+            assert_eq!(game.state(), state);
+
+            check_step(&mut game, &[Direction::Noop, Direction::Down]);
+            assert_eq!(game.moves, 2);
+        }
+    }
+
+    #[test]
+    fn test_reached_target() {
+        #[rustfmt::skip]
+        let board = [
+            16, 16, 0, 0,
+            0,  0,  0, 0,
+            0,  0,  0, 0,
+            0,  0,  0, 0,
+        ];
+        let config = GameConfig {
+            target: 32,
+            ..GameConfig::default()
+        };
+
+        let mut game = Game::<Fr>::new(board, config);
+        check_step(&mut game, &[Direction::Up]);
+        assert_eq!(game.max_tile, 16);
+        assert!(!game.reached);
+
+        check_step(&mut game, &[Direction::Left, Direction::Right]);
+        assert_eq!(game.max_tile, 32);
+        assert!(game.reached);
+        assert_eq!(game.reached_at, 2);
+    }
+
+    #[test]
+    fn test_game_over() {
+        #[rustfmt::skip]
+        let board = [
+            2, 4, 2, 4,
+            4, 2, 4, 2,
+            2, 4, 2, 4,
+            4, 2, 4, 2,
+        ];
+
+        let mut game = Game::<Fr>::new(board, GameConfig::default());
+        check_step(&mut game, &[Direction::Up]);
+        assert!(game.game_over);
+        assert_eq!(game.moves, 1);
+
+        // The moves after the end of the game are not counted.
+        check_step(&mut game, &[Direction::Left, Direction::Down]);
+        assert_eq!(game.moves, 1);
+
+        // Even if the board could move.
+        let mut game = Game::<Fr>::new(BOARD, GameConfig::default());
+        game.game_over = true;
+        let state = game.state();
+        check_step(&mut game, &[Direction::Left, Direction::Down]);
+        assert_eq!(game.state(), state);
+    }
+
+    #[test]
+    fn test_private_board() {
+        let config = GameConfig {
+            private_board: true,
+            ..GameConfig::default()
+        };
+        let directions = [Direction::Left, Direction::Down];
+        let bits: Vec<_> = directions.iter().map(|d| d.to_bits()).collect();
+        let (blinding, next_blinding) = (Fr::from(1234), Fr::from(5678));
+
+        // Synthesizes from `game` committed under `blinding`, opened with `opening`.
+        let synthesize = |game: &Game<Fr>, blinding: Fr, opening: Fr| {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let mut z = Vec::new();
+            for (i, x) in game.private_z(blinding).iter().enumerate() {
+                z.push(AllocatedNum::alloc(cs.namespace(|| format!("z_{i}")), || Ok(*x)).unwrap());
+            }
+
+            let circuit = Game2048Circuit::with_config(&bits, config)
+                .with_private_input(game.private_input(opening, next_blinding));
+            assert_eq!(circuit.arity(), z.len());
+            let output = circuit.synthesize(&mut cs, &z).unwrap();
+
+            let output: Vec<_> = output.iter().map(|x| x.get_value().unwrap()).collect();
+            (cs.is_satisfied(), output)
+        };
+
+        let mut game = Game::<Fr>::new(BOARD, config);
+        let (satisfied, output) = synthesize(&game, blinding, blinding);
+        assert!(satisfied);
+
+        for direction in directions {
+            game.play(direction);
+        }
+        assert_eq!(output, game.private_z(next_blinding));
+        assert_eq!(output[3..], [Fr::from(game.score), Fr::from(game.max_tile)]);
+
+        // The opening must match the commitment in `z`.
+        let game = Game::<Fr>::new(BOARD, config);
+        let (satisfied, _) = synthesize(&game, blinding, next_blinding);
+        assert!(!satisfied);
+    }
+
+    #[test]
+    fn test_packed_board() {
+        let directions = [Direction::Left, Direction::Down, Direction::Right];
+
+        let mut num_constraints = vec![];
+        for packed_board in [false, true] {
+            let config = GameConfig {
+                packed_board,
+                ..GameConfig::default()
+            };
+            let mut game = Game::<Fr>::new(BOARD, config);
+            num_constraints.push(check_step(&mut game, &directions));
+            check_step(&mut game, &directions);
+        }
+
+        // Unpacking and repacking cost constraints once per step, not per move.
+        assert!(num_constraints[1] > num_constraints[0]);
+        assert!(num_constraints[1] - num_constraints[0] < 1000);
+
+        // But the recursive verifier hashes a smaller `z`, which saves more.
+        let num_constraints: Vec<_> = [false, true]
+            .into_iter()
+            .map(|packed_board| {
+                let config = GameConfig {
+                    packed_board,
+                    ..GameConfig::default()
+                };
+                let prover = GameProver::<E1, E2>::setup(config, 1);
+                prover.public_params().num_constraints().0
+            })
+            .collect();
+        assert!(num_constraints[1] + 2000 < num_constraints[0]);
+    }
+
+    #[test]
+    fn test_merges() {
+        #[rustfmt::skip]
+        let board = [
+            2, 2, 4, 4,
+            0, 0, 0, 0,
+            8, 0, 8, 0,
+            2, 0, 0, 0,
+        ];
+
+        // The merges are checked against the sum of the tiles too.
+        let config = GameConfig {
+            check_tile_sum: true,
+            ..GameConfig::default()
+        };
+        let mut game = Game::<Fr>::new(board, config);
+        check_step(&mut game, &[Direction::Left]);
+        assert_eq!(game.merges[..3], [1, 1, 1]);
+        assert_eq!(game.merges[3..], [0; 13]);
+
+        // The counters never decrease.
+        check_step(
+            &mut game,
+            &[Direction::Down, Direction::Right, Direction::Up],
+        );
+        assert!(game.merges[..3].iter().all(|x| *x >= 1));
+    }
+
+    #[test]
+    fn test_claim() {
+        let config = GameConfig {
+            private_board: true,
+            claim: true,
+            ..GameConfig::default()
+        };
+        #[rustfmt::skip]
+        let board = [
+            2, 2, 4, 4,
+            0, 0, 0, 0,
+            8, 0, 8, 0,
+            2, 0, 0, 0,
+        ];
+        let bits = [Direction::Left.to_bits()];
+        let (blinding, next_blinding) = (Fr::from(1234), Fr::from(5678));
+
+        // Moving left scores 4 + 8 + 16 and creates a 16.
+        for (score_threshold, max_tile_threshold, holds) in [
+            (0, 0, true),
+            (28, 16, true),
+            (29, 16, false),
+            (28, 32, false),
+        ] {
+            let mut game = Game::<Fr>::new(board, config);
+            let z0 = game.claim_z(blinding, score_threshold, max_tile_threshold);
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let mut z = Vec::new();
+            for (i, x) in z0.iter().enumerate() {
+                z.push(AllocatedNum::alloc(cs.namespace(|| format!("z_{i}")), || Ok(*x)).unwrap());
+            }
+
+            let circuit = Game2048Circuit::with_config(&bits, config)
+                .with_private_input(game.private_input(blinding, next_blinding));
+            assert_eq!(circuit.arity(), z.len());
+            let output = circuit.synthesize(&mut cs, &z).unwrap();
+            assert!(cs.is_satisfied());
+
+            game.play(Direction::Left);
+            let output: Vec<_> = output.iter().map(|x| x.get_value().unwrap()).collect();
+            assert_eq!(
+                output,
+                game.claim_z(next_blinding, score_threshold, max_tile_threshold)
+            );
+            assert_eq!(output[5], Fr::from(holds as u64));
+        }
+    }
+
+    #[test]
+    fn test_board_history() {
+        for count_stuck_moves in [true, false] {
+            let config = GameConfig {
+                board_history: true,
+                count_stuck_moves,
+                ..GameConfig::default()
+            };
+            let directions = [Direction::Left, Direction::Left, Direction::Down];
+
+            let initial = Game::<Fr>::new(BOARD, config);
+            let mut game = initial.clone();
+            check_step(&mut game, &directions);
+            check_step(&mut game, &directions);
+            assert_eq!(game.history.boards.len() as u64, game.moves);
+
+            // The board after every counted move opens against the final root.
+            let log: Vec<_> = directions
+                .iter()
+                .chain(directions.iter())
+                .copied()
+                .collect();
+            for k in 1..=game.moves {
+                let opening = initial.clone().open_history(&log, k).unwrap();
+                assert!(opening.verify(game.state().history));
+            }
+        }
+    }
+
+    #[test]
+    fn test_score() {
+        #[rustfmt::skip]
+        let board = [
+            2, 2, 4, 4,
+            0, 0, 0, 0,
+            8, 0, 8, 0,
+            2, 0, 0, 0,
+        ];
+
+        // The merges are checked against the sum of the tiles too.
+        let config = GameConfig {
+            check_tile_sum: true,
+            ..GameConfig::default()
+        };
+        let mut game = Game::<Fr>::new(board, config);
+        check_step(&mut game, &[Direction::Left]);
+        assert_eq!(game.score, 4 + 8 + 16);
+
+        check_step(
+            &mut game,
+            &[Direction::Right, Direction::Up, Direction::Down],
+        );
+        assert!(game.score >= 4 + 8 + 16);
+    }
+}
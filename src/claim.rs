@@ -0,0 +1,111 @@
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use crate::max_tile::greater_or_equal;
+
+/// Returns true if score >= score_threshold and max_tile >= max_tile_threshold.
+pub fn claim_holds(
+    score: u64,
+    max_tile: u64,
+    score_threshold: u64,
+    max_tile_threshold: u64,
+) -> bool {
+    score >= score_threshold && max_tile >= max_tile_threshold
+}
+
+/// The circuit version of `claim_holds`, which decides the claim of a proof in
+/// claim mode. A threshold of 0 always holds.
+///
+/// All the values must be less than 2^NUM_TILE_BITS, which the score of a 4x4
+/// board cannot reach.
+pub struct Claim<F: PrimeField> {
+    pub score: AllocatedNum<F>,
+
+    pub max_tile: AllocatedNum<F>,
+
+    pub score_threshold: AllocatedNum<F>,
+
+    pub max_tile_threshold: AllocatedNum<F>,
+}
+
+impl<F: PrimeField> Claim<F> {
+    pub fn new(
+        score: &AllocatedNum<F>,
+        max_tile: &AllocatedNum<F>,
+        score_threshold: &AllocatedNum<F>,
+        max_tile_threshold: &AllocatedNum<F>,
+    ) -> Self {
+        Self {
+            score: score.clone(),
+            max_tile: max_tile.clone(),
+            score_threshold: score_threshold.clone(),
+            max_tile_threshold: max_tile_threshold.clone(),
+        }
+    }
+
+    /// Returns 1 if the claim holds, 0 otherwise.
+    pub fn synthesize<CS: ConstraintSystem<F>>(
+        &self,
+        mut cs: CS,
+    ) -> Result<AllocatedNum<F>, SynthesisError> {
+        let score_bit =
+            greater_or_equal(cs.namespace(|| "score"), &self.score, &self.score_threshold)?;
+        let max_tile_bit = greater_or_equal(
+            cs.namespace(|| "max_tile"),
+            &self.max_tile,
+            &self.max_tile_threshold,
+        )?;
+
+        let holds =
+            score_bit.get_value().unwrap_or(false) && max_tile_bit.get_value().unwrap_or(false);
+        let holds_var = AllocatedNum::alloc(cs.namespace(|| "alloc_holds"), || {
+            Ok(if holds { F::ONE } else { F::ZERO })
+        })?;
+
+        cs.enforce(
+            || "enforce_(score_bit * max_tile_bit = holds)",
+            |lc| lc + score_bit.get_variable(),
+            |lc| lc + max_tile_bit.get_variable(),
+            |lc| lc + holds_var.get_variable(),
+        );
+
+        Ok(holds_var)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+    use blstrs::Scalar as Fr;
+
+    use super::{claim_holds, Claim};
+
+    #[test]
+    fn test_claim() {
+        for (score, max_tile, score_threshold, max_tile_threshold) in [
+            (10000, 1024, 10000, 1024),
+            (9999, 1024, 10000, 0),
+            (20000, 512, 0, 1024),
+            (0, 0, 0, 0),
+        ] {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let mut vars = Vec::new();
+            for (i, x) in [score, max_tile, score_threshold, max_tile_threshold]
+                .iter()
+                .enumerate()
+            {
+                vars.push(
+                    AllocatedNum::alloc(cs.namespace(|| format!("input_{i}")), || Ok(Fr::from(*x)))
+                        .unwrap(),
+                );
+            }
+
+            let circuit = Claim::new(&vars[0], &vars[1], &vars[2], &vars[3]);
+            let holds = circuit.synthesize(cs.namespace(|| "claim")).unwrap();
+
+            let expected = claim_holds(score, max_tile, score_threshold, max_tile_threshold);
+            assert_eq!(holds.get_value(), Some(Fr::from(expected as u64)));
+            assert!(cs.is_satisfied());
+        }
+    }
+}
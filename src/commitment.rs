@@ -0,0 +1,244 @@
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use crate::{
+    circuit::GameConfig,
+    game::{is_start_board, try_to_u64, Game},
+    max_tile::NUM_TILE_BITS,
+    poseidon::{hash, hash_circuit},
+    state::GameState,
+};
+
+/// Returns the hiding commitment hash(board, transcript, blinding), which
+/// `commit_state` commits to in place of the board and the transcript.
+pub fn commit<F: PrimeField>(board: &[F], transcript: F, blinding: F) -> F {
+    assert_eq!(board.len(), 16);
+
+    let mut preimage = board.to_vec();
+    preimage.push(transcript);
+    preimage.push(blinding);
+
+    hash(&preimage)
+}
+
+/// The circuit version of `commit`.
+pub fn commit_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+    cs: CS,
+    board: &[AllocatedNum<F>],
+    transcript: &AllocatedNum<F>,
+    blinding: &AllocatedNum<F>,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+    assert_eq!(board.len(), 16);
+
+    let mut preimage = board.to_vec();
+    preimage.push(transcript.clone());
+    preimage.push(blinding.clone());
+
+    hash_circuit(cs, &preimage)
+}
+
+/// Returns the hiding commitment to every element of `state` but the player
+/// and the nonce: hash(commit(board, transcript, blinding), score, ...).
+///
+/// In private-board and claim modes `z` carries this commitment, see
+/// `GameConfig::private_board`.
+pub fn commit_state<F: PrimeField>(state: &GameState<F>, blinding: F) -> F {
+    let mut preimage = vec![commit(&state.board, state.transcript, blinding)];
+    preimage.extend_from_slice(&state.to_z()[GameState::<F>::SUMMARY_INDEX..]);
+
+    hash(&preimage)
+}
+
+/// The circuit version of `commit_state`.
+pub fn commit_state_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    state: &GameState<AllocatedNum<F>>,
+    blinding: &AllocatedNum<F>,
+) -> Result<AllocatedNum<F>, SynthesisError> {
+    let inner = commit_circuit(
+        cs.namespace(|| "commit_board"),
+        &state.board,
+        &state.transcript,
+        blinding,
+    )?;
+
+    let mut preimage = vec![inner];
+    preimage.extend_from_slice(&state.to_z()[GameState::<F>::SUMMARY_INDEX..]);
+
+    hash_circuit(cs.namespace(|| "commit_state"), &preimage)
+}
+
+/// The private inputs of a step in private-board and claim modes: the opening
+/// of the commitment in `z`, and the blinding of the commitment the step
+/// outputs. Only the committed elements of `state` are used.
+///
+/// The blinding of the output is fresh, so whoever made `z0` (and knows its
+/// blinding) cannot open the commitments of the later steps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrivateInput<F: PrimeField> {
+    pub state: GameState<F>,
+
+    pub blinding: F,
+
+    pub next_blinding: F,
+}
+
+impl<F: PrimeField> Default for PrivateInput<F> {
+    fn default() -> Self {
+        Self {
+            state: GameState::from_z(&[F::ZERO; GameState::<()>::ARITY]),
+            blinding: F::ZERO,
+            next_blinding: F::ZERO,
+        }
+    }
+}
+
+/// The opening of the commitment in the `z0` of a new game in private-board
+/// and claim modes: the board the game starts from and the blinding of the
+/// commitment.
+///
+/// The circuit takes the committed state of `z0` as it is, so a verifier
+/// only knows that a proof starts a new game once the prover opens `z0`, see
+/// `GameProver::start_opening`. Opening it reveals nothing of the game: the
+/// board of a new game is public anyway (see `start_board`), and the later
+/// commitments have fresh blindings, see `PrivateInput`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartOpening<F: PrimeField> {
+    pub board: [u64; 16],
+
+    pub blinding: F,
+}
+
+impl<F: PrimeField> StartOpening<F> {
+    /// Returns true if `z0` commits to a new game from `board` under
+    /// `blinding`, with the player and the nonce it reveals, and `board` is
+    /// the board of a new game, see `is_start_board`. In claim mode the
+    /// thresholds of `z0` must be less than 2^NUM_TILE_BITS, see `Claim`.
+    pub fn opens_new_game(&self, z0: &[F], config: GameConfig) -> bool {
+        let arity = if config.claim {
+            GameState::<F>::CLAIM_ARITY
+        } else {
+            GameState::<F>::PRIVATE_ARITY
+        };
+        if !config.private_board || z0.len() != arity {
+            return false;
+        }
+
+        let game = Game::new(self.board, config)
+            .with_player(z0[1])
+            .with_nonce(z0[2]);
+        let expected = if config.claim {
+            let threshold = |x| try_to_u64(x).filter(|x| *x < 1 << NUM_TILE_BITS);
+            match (threshold(z0[3]), threshold(z0[4])) {
+                (Some(score_threshold), Some(max_tile_threshold)) => {
+                    game.claim_z(self.blinding, score_threshold, max_tile_threshold)
+                }
+                _ => return false,
+            }
+        } else {
+            game.private_z(self.blinding)
+        };
+
+        is_start_board(&self.board) && expected == z0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+    use blstrs::Scalar as Fr;
+
+    use crate::{
+        circuit::GameConfig,
+        game::{start_board, Game},
+    };
+
+    use super::{commit, commit_circuit, StartOpening};
+
+    #[test]
+    fn test_commit() {
+        let board: Vec<_> = (0..16).map(|i| Fr::from(i * 2)).collect();
+        let transcript = Fr::from(7);
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let mut board_vars = Vec::new();
+        for (i, x) in board.iter().enumerate() {
+            board_vars.push(
+                AllocatedNum::alloc(cs.namespace(|| format!("board_{i}")), || Ok(*x)).unwrap(),
+            );
+        }
+        let transcript_var =
+            AllocatedNum::alloc(cs.namespace(|| "transcript"), || Ok(transcript)).unwrap();
+        let blinding_var =
+            AllocatedNum::alloc(cs.namespace(|| "blinding"), || Ok(Fr::from(42))).unwrap();
+
+        let commitment = commit_circuit(
+            cs.namespace(|| "commit"),
+            &board_vars,
+            &transcript_var,
+            &blinding_var,
+        )
+        .unwrap();
+
+        assert!(cs.is_satisfied());
+        assert_eq!(
+            commitment.get_value(),
+            Some(commit(&board, transcript, Fr::from(42)))
+        );
+
+        // The same board under another blinding gives another commitment.
+        assert_ne!(
+            commit(&board, transcript, Fr::from(42)),
+            commit(&board, transcript, Fr::from(43))
+        );
+    }
+
+    #[test]
+    fn test_start_opening() {
+        let config = GameConfig {
+            private_board: true,
+            ..GameConfig::default()
+        };
+        let opening = StartOpening {
+            board: start_board(7),
+            blinding: Fr::from(42),
+        };
+        let game = Game::<Fr>::new(opening.board, config).with_nonce(Fr::from(7));
+        assert!(opening.opens_new_game(&game.private_z(opening.blinding), config));
+        assert!(!opening.opens_new_game(&game.private_z(Fr::from(43)), config));
+
+        // A game which already started.
+        let mut played = game.clone();
+        played.score = 100;
+        assert!(!opening.opens_new_game(&played.private_z(opening.blinding), config));
+
+        // A board no new game has, even if `z0` commits to it.
+        let opening = StartOpening {
+            board: [1024; 16],
+            blinding: Fr::from(42),
+        };
+        let game = Game::<Fr>::new(opening.board, config);
+        assert!(!opening.opens_new_game(&game.private_z(opening.blinding), config));
+
+        let config = GameConfig {
+            claim: true,
+            ..config
+        };
+        let opening = StartOpening {
+            board: start_board(7),
+            blinding: Fr::from(42),
+        };
+        let game = Game::<Fr>::new(opening.board, config);
+        let z0 = game.claim_z(opening.blinding, 1000, 128);
+        assert!(opening.opens_new_game(&z0, config));
+
+        // A claim which holds from the start.
+        let mut z0 = z0;
+        z0[5] = Fr::from(1);
+        assert!(!opening.opens_new_game(&z0, config));
+
+        // Thresholds out of the range of `Claim`.
+        let z0 = game.claim_z(opening.blinding, 1 << 40, 128);
+        assert!(!opening.opens_new_game(&z0, config));
+    }
+}
@@ -3,12 +3,18 @@ use ff::PrimeField;
 
 use crate::NumConstraintSystem;
 
+/// Picks the lines of the board to slide towards their first cell.
+///
+/// The direction is one-hot, or all zeros for a no-op: then every line is
+/// zero, and `is_noop` is 1.
 pub struct DirectionChooser<F: PrimeField> {
     pub board: Vec<AllocatedNum<F>>,
 
     pub lines: Vec<Vec<AllocatedNum<F>>>,
 
     pub direction: Vec<AllocatedNum<F>>,
+
+    pub is_noop: Option<AllocatedNum<F>>,
 }
 
 impl<F: PrimeField> DirectionChooser<F> {
@@ -20,6 +26,7 @@ impl<F: PrimeField> DirectionChooser<F> {
             board: board.to_vec(),
             lines: vec![],
             direction: direction.to_vec(),
+            is_noop: None,
         }
     }
 
@@ -35,12 +42,17 @@ impl<F: PrimeField> DirectionChooser<F> {
 
         let sum_direction =
             AllocatedNum::sum(cs.namespace(|| "sum_of_direction"), &self.direction)?;
+        let is_noop = AllocatedNum::alloc(cs.namespace(|| "alloc_is_noop"), || {
+            Ok(F::ONE - sum_direction.get_value().unwrap_or(F::ZERO))
+        })?;
         cs.enforce(
-            || "enforce `sum_direction` is equal to one",
-            |lc| lc,
-            |lc| lc,
+            || "enforce_(is_noop = 1 - sum_direction)",
+            |lc| lc + is_noop.get_variable(),
+            |lc| lc + CS::one(),
             |lc| lc + CS::one() - sum_direction.get_variable(),
         );
+        is_noop.apply_bool_constraint(cs.namespace(|| "is_noop_is_boolean"));
+        self.is_noop = Some(is_noop);
 
         let line_0 = vec![
             AllocatedNum::product_sum(
@@ -451,4 +463,53 @@ mod test {
         assert_eq!(lines[2], vec![board[11], board[10], board[9], board[8]]);
         assert_eq!(lines[3], vec![board[15], board[14], board[13], board[12]]);
     }
+
+    fn synthesize_noop(direction: [u64; 4]) -> (bool, Vec<Fr>, Fr) {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+
+        let mut board_vars = Vec::new();
+        for i in 0..16 {
+            board_vars.push(
+                AllocatedNum::alloc(cs.namespace(|| format!("board_{i}")), || Ok(Fr::from(2)))
+                    .unwrap(),
+            );
+        }
+        let mut direction_vars = Vec::new();
+        for (i, x) in direction.iter().enumerate() {
+            direction_vars.push(
+                AllocatedNum::alloc(cs.namespace(|| format!("direction_{i}")), || {
+                    Ok(Fr::from(*x))
+                })
+                .unwrap(),
+            );
+        }
+
+        let mut circuit = DirectionChooser::new(&board_vars, &direction_vars);
+        circuit.synthesize(&mut cs).unwrap();
+
+        let lines = circuit
+            .lines
+            .iter()
+            .flatten()
+            .map(|x| x.get_value().unwrap())
+            .collect();
+        let is_noop = circuit.is_noop.unwrap().get_value().unwrap();
+        (cs.is_satisfied(), lines, is_noop)
+    }
+
+    #[test]
+    fn test_noop() {
+        let (satisfied, lines, is_noop) = synthesize_noop([0, 0, 0, 0]);
+        assert!(satisfied);
+        assert_eq!(lines, vec![Fr::ZERO; 16]);
+        assert_eq!(is_noop, Fr::ONE);
+
+        let (satisfied, _, is_noop) = synthesize_noop([0, 0, 1, 0]);
+        assert!(satisfied);
+        assert_eq!(is_noop, Fr::ZERO);
+
+        // Two directions at once.
+        let (satisfied, _, _) = synthesize_noop([1, 0, 1, 0]);
+        assert!(!satisfied);
+    }
 }
@@ -0,0 +1,639 @@
+//! The rules of `Game2048Circuit`, out of the circuit.
+//!
+//! `Game` reproduces the states that the circuit computes, so a move list
+//! can be replayed and checked against the state of a proof.
+
+use std::{fmt, str::FromStr};
+
+use ff::PrimeField;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    circuit::GameConfig,
+    claim::claim_holds,
+    commitment::{commit_state, PrivateInput},
+    gen_next::NUM_SEED_BITS,
+    history::{BoardHistory, HistoryOpening, HISTORY_DEPTH},
+    merge_counter::{level, NUM_LEVELS},
+    packing::pack,
+    poseidon::{hash, MAX_PREIMAGE_LEN},
+    state::GameState,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+
+    /// Leaves the state untouched and spawns nothing, to pad the last step
+    /// of a game.
+    Noop,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
+
+    /// The one-hot encoding `[up, down, left, right]` taken by the circuit,
+    /// all zeros for `Noop`.
+    pub fn to_bits<F: PrimeField>(self) -> [F; 4] {
+        let mut bits = [F::ZERO; 4];
+        if self != Direction::Noop {
+            bits[self as usize] = F::ONE;
+        }
+
+        bits
+    }
+
+    /// The code absorbed by the transcript, see `Transcript`.
+    pub fn code(self) -> u64 {
+        match self {
+            Direction::Noop => 0,
+            _ => self as u64 + 1,
+        }
+    }
+
+    /// Returns the board indices of `lines[i][j]`, following `DirectionChooser`.
+    fn cell(self, i: usize, j: usize) -> usize {
+        match self {
+            Direction::Up => 4 * j + i,
+            Direction::Down => 4 * (3 - j) + i,
+            Direction::Left => 4 * i + j,
+            Direction::Right => 4 * i + 3 - j,
+            Direction::Noop => unreachable!("a no-op does not slide the board"),
+        }
+    }
+}
+
+/// The reason a move cannot be parsed, see `Direction::from_str`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDirectionError(pub String);
+
+impl fmt::Display for ParseDirectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown move {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseDirectionError {}
+
+impl FromStr for Direction {
+    type Err = ParseDirectionError;
+
+    /// Parses a move as its name or its initial, in any case: `up`, `U`,
+    /// `left`, `noop`...
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "u" | "up" => Ok(Direction::Up),
+            "d" | "down" => Ok(Direction::Down),
+            "l" | "left" => Ok(Direction::Left),
+            "r" | "right" => Ok(Direction::Right),
+            "n" | "noop" => Ok(Direction::Noop),
+            _ => Err(ParseDirectionError(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Direction {
+    /// Writes the name of a move, which `Direction::from_str` parses back.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::Noop => "noop",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Slides a line towards its first cell, merging equal neighbours once.
+/// Returns the new line and the numbers created by merges.
+fn slide(line: [u64; 4]) -> ([u64; 4], Vec<u64>) {
+    let tiles: Vec<_> = line.iter().copied().filter(|x| *x != 0).collect();
+
+    let mut result = [0; 4];
+    let mut merged = vec![];
+    let mut len = 0;
+    let mut i = 0;
+    while i < tiles.len() {
+        if i + 1 < tiles.len() && tiles[i] == tiles[i + 1] {
+            result[len] = tiles[i] * 2;
+            merged.push(tiles[i] * 2);
+            i += 2;
+        } else {
+            result[len] = tiles[i];
+            i += 1;
+        }
+        len += 1;
+    }
+
+    (result, merged)
+}
+
+/// Returns the player identifier of an account (e.g. an account ID or the
+/// bytes of a public key), to be put in `GameState::player`.
+pub fn player_id<F: PrimeField>(account: &[u8]) -> F {
+    // Pack 31 bytes per element, so every chunk fits in the field.
+    let mut preimage: Vec<F> = account
+        .chunks(31)
+        .map(|chunk| {
+            let mut repr = F::Repr::default();
+            repr.as_mut()[..chunk.len()].copy_from_slice(chunk);
+            F::from_repr(repr).unwrap()
+        })
+        .collect();
+    preimage.push(F::from(account.len() as u64));
+
+    // Chain the chunks of long accounts.
+    while preimage.len() > MAX_PREIMAGE_LEN {
+        let tail = preimage.split_off(MAX_PREIMAGE_LEN - 1);
+        preimage.push(hash(&tail));
+    }
+
+    hash(&preimage)
+}
+
+/// Converts a counter of the state to an integer.
+pub fn to_u64<F: PrimeField>(x: F) -> u64 {
+    try_to_u64(x).expect("the state holds a value above u64::MAX")
+}
+
+/// Converts a counter of the state to an integer, or returns `None` if it is
+/// above `u64::MAX`.
+pub fn try_to_u64<F: PrimeField>(x: F) -> Option<u64> {
+    let repr = x.to_repr();
+    let value = u64::from_le_bytes(repr.as_ref()[..8].try_into().unwrap());
+
+    (F::from(value) == x).then_some(value)
+}
+
+/// The largest tile of a 4x4 board, see `NUM_LEVELS`.
+pub const MAX_TILE: u64 = 1 << (NUM_LEVELS + 1);
+
+/// The bound of the counters of a state which does not come from a game, so
+/// that playing from it cannot overflow them, see `Game::try_from_z`.
+pub const MAX_COUNTER: u64 = 1 << 62;
+
+/// Returns a board with two 2s, placed from `nonce`.
+pub fn start_board(nonce: u64) -> [u64; 16] {
+    let first = nonce % 16;
+    let second = (first + 1 + nonce / 16 % 15) % 16;
+
+    let mut board = [0; 16];
+    board[first as usize] = 2;
+    board[second as usize] = 2;
+
+    board
+}
+
+/// Returns true if a new game can start from `board`: it has at most two
+/// tiles, each a 2 or a 4, like the boards of `start_board`.
+pub fn is_start_board(board: &[u64; 16]) -> bool {
+    let tiles: Vec<_> = board.iter().filter(|x| **x != 0).collect();
+
+    tiles.len() <= 2 && tiles.iter().all(|x| **x == 2 || **x == 4)
+}
+
+/// Returns true if no move can change `board`, see `GameOver`.
+pub fn is_game_over(board: &[u64; 16]) -> bool {
+    for i in 0..4 {
+        for j in 0..4 {
+            if board[4 * i + j] == 0 {
+                return false;
+            }
+            if j < 3 && board[4 * i + j] == board[4 * i + j + 1] {
+                return false;
+            }
+            if i < 3 && board[4 * i + j] == board[4 * (i + 1) + j] {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// A game of 2048 played with the rules of `Game2048Circuit`.
+#[derive(Debug, Clone)]
+pub struct Game<F: PrimeField> {
+    pub board: [u64; 16],
+
+    pub transcript: F,
+
+    pub player: F,
+
+    pub nonce: F,
+
+    pub score: u64,
+
+    pub moves: u64,
+
+    pub max_tile: u64,
+
+    pub reached: bool,
+
+    pub reached_at: u64,
+
+    pub game_over: bool,
+
+    pub merges: [u64; NUM_LEVELS],
+
+    pub history: BoardHistory<F>,
+
+    pub config: GameConfig,
+}
+
+impl<F: PrimeField> Game<F> {
+    pub fn new(board: [u64; 16], config: GameConfig) -> Self {
+        Self {
+            board,
+            transcript: F::ZERO,
+            player: F::ZERO,
+            nonce: F::ZERO,
+            score: 0,
+            moves: 0,
+            max_tile: 0,
+            reached: false,
+            reached_at: 0,
+            game_over: false,
+            merges: [0; NUM_LEVELS],
+            history: BoardHistory::default(),
+            config,
+        }
+    }
+
+    /// Resumes a game from the state `z` of the circuit.
+    ///
+    /// The boards of the history are not in `z`, so a game with a board
+    /// history can only be resumed before its first move.
+    pub fn from_state(state: &GameState<F>, config: GameConfig) -> Self {
+        assert_eq!(state.board.len(), 16);
+        if config.board_history {
+            assert!(
+                state.history == BoardHistory::<F>::empty_root(),
+                "cannot resume the board history"
+            );
+        }
+
+        Self {
+            board: std::array::from_fn(|i| to_u64(state.board[i])),
+            transcript: state.transcript,
+            player: state.player,
+            nonce: state.nonce,
+            score: to_u64(state.score),
+            moves: to_u64(state.moves),
+            max_tile: to_u64(state.max_tile),
+            reached: to_u64(state.reached) == 1,
+            reached_at: to_u64(state.reached_at),
+            game_over: to_u64(state.game_over) == 1,
+            merges: std::array::from_fn(|k| to_u64(state.merges[k])),
+            history: BoardHistory::default(),
+            config,
+        }
+    }
+
+    /// Resumes a game from a state `z` which may not come from a game, e.g.
+    /// the `z0` of a proof to verify. Returns `None` unless `z` has
+    /// `GameState::ARITY` elements, tiles which are 0 or powers of 2 up to
+    /// `MAX_TILE`, counters below `MAX_COUNTER`, flags which are 0 or 1, and
+    /// with a board history, the root of the empty tree, see `from_state`.
+    pub fn try_from_z(z: &[F], config: GameConfig) -> Option<Self> {
+        if z.len() != GameState::<F>::ARITY {
+            return None;
+        }
+        let state = GameState::from_z(z);
+
+        let tiles_ok = state.board.iter().all(|x| {
+            try_to_u64(*x)
+                .is_some_and(|x| x == 0 || ((2..=MAX_TILE).contains(&x) && x.is_power_of_two()))
+        });
+        let counters_ok = [state.score, state.moves, state.max_tile, state.reached_at]
+            .iter()
+            .chain(&state.merges)
+            .all(|x| try_to_u64(*x).is_some_and(|x| x < MAX_COUNTER));
+        let flags_ok = [state.reached, state.game_over]
+            .iter()
+            .all(|x| *x == F::ZERO || *x == F::ONE);
+        let history_ok = !config.board_history || state.history == BoardHistory::<F>::empty_root();
+        if !(tiles_ok && counters_ok && flags_ok && history_ok) {
+            return None;
+        }
+
+        Some(Self::from_state(&state, config))
+    }
+
+    /// Plays the game as `player`, see `player_id`.
+    pub fn with_player(mut self, player: F) -> Self {
+        self.player = player;
+        self
+    }
+
+    /// Plays the game in the session of `nonce`.
+    pub fn with_nonce(mut self, nonce: F) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Returns the state `z` of the circuit.
+    pub fn state(&self) -> GameState<F> {
+        GameState {
+            board: self.board.iter().map(|x| F::from(*x)).collect(),
+            transcript: self.transcript,
+            player: self.player,
+            nonce: self.nonce,
+            score: F::from(self.score),
+            moves: F::from(self.moves),
+            max_tile: F::from(self.max_tile),
+            reached: F::from(self.reached as u64),
+            reached_at: F::from(self.reached_at),
+            game_over: F::from(self.game_over as u64),
+            merges: self.merges.iter().map(|x| F::from(*x)).collect(),
+            history: if self.config.board_history {
+                self.history.root()
+            } else {
+                F::ZERO
+            },
+        }
+    }
+
+    /// Returns the state `z` of the circuit in private-board mode, with the
+    /// state committed under `blinding`.
+    pub fn private_z(&self, blinding: F) -> Vec<F> {
+        let state = self.state();
+        state.to_private_z(commit_state(&state, blinding))
+    }
+
+    /// Returns the state `z` of the circuit in packed-board mode.
+    pub fn packed_z(&self) -> Vec<F> {
+        let state = self.state();
+        state.to_packed_z(pack(&state.board))
+    }
+
+    /// Returns the state `z` of the circuit in claim mode, with the state
+    /// committed under `blinding`, see `claim_holds`.
+    pub fn claim_z(&self, blinding: F, score_threshold: u64, max_tile_threshold: u64) -> Vec<F> {
+        let state = self.state();
+        let holds = claim_holds(
+            self.score,
+            self.max_tile,
+            score_threshold,
+            max_tile_threshold,
+        );
+
+        state.to_claim_z(
+            commit_state(&state, blinding),
+            F::from(score_threshold),
+            F::from(max_tile_threshold),
+            F::from(holds as u64),
+        )
+    }
+
+    /// Returns the private inputs of a step from this state in private-board
+    /// mode, see `private_z`.
+    pub fn private_input(&self, blinding: F, next_blinding: F) -> PrivateInput<F> {
+        PrivateInput {
+            state: self.state(),
+            blinding,
+            next_blinding,
+        }
+    }
+
+    /// Returns the paths of the appends to the board history of `moves` played
+    /// from this game, see `Game2048Circuit::with_history_paths`, or `None` if
+    /// the appends do not fit in the history. The moves are replayed without
+    /// the history, which is only read.
+    pub fn history_paths(&self, moves: &[Direction]) -> Option<Vec<Vec<F>>> {
+        let mut replay = Self {
+            board: self.board,
+            transcript: self.transcript,
+            player: self.player,
+            nonce: self.nonce,
+            score: self.score,
+            moves: self.moves,
+            max_tile: self.max_tile,
+            reached: self.reached,
+            reached_at: self.reached_at,
+            game_over: self.game_over,
+            merges: self.merges,
+            history: BoardHistory::default(),
+            config: GameConfig {
+                board_history: false,
+                ..self.config
+            },
+        };
+
+        // A move appends its board if and only if it is counted.
+        let appends: Vec<_> = moves
+            .iter()
+            .map(|direction| {
+                let counted = replay.moves;
+                replay.play(*direction);
+                (replay.moves != counted).then_some(replay.board)
+            })
+            .collect();
+        let len = self.history.boards.len() + appends.iter().flatten().count();
+        if len > 1 << HISTORY_DEPTH {
+            return None;
+        }
+
+        Some(self.history.next_paths(&appends))
+    }
+
+    /// Replays the game log `moves` and opens the board after move `k` in the
+    /// board history, see `GameConfig::board_history`.
+    pub fn open_history(mut self, moves: &[Direction], k: u64) -> Option<HistoryOpening<F>> {
+        assert!(self.config.board_history);
+
+        for direction in moves {
+            self.play(*direction);
+        }
+
+        self.history.open(k)
+    }
+
+    /// Plays a move, mirroring one iteration of `Game2048Circuit::synthesize`.
+    pub fn play(&mut self, direction: Direction) {
+        if self.game_over || direction == Direction::Noop {
+            return;
+        }
+
+        let mut board = self.board;
+        for i in 0..4 {
+            let line = [0, 1, 2, 3].map(|j| self.board[direction.cell(i, j)]);
+            let (line, merged) = slide(line);
+            for (j, x) in line.into_iter().enumerate() {
+                board[direction.cell(i, j)] = x;
+            }
+            self.score += merged.iter().sum::<u64>();
+            for tile in merged {
+                self.merges[level(tile)] += 1;
+            }
+        }
+        let moveable = board != self.board;
+        let counted = moveable || self.config.count_stuck_moves;
+        if counted {
+            self.moves += 1;
+        }
+
+        let code = F::from(direction.code());
+        let seed = hash(&[self.player, self.nonce, self.transcript, code]);
+        let (new_index, new_number) = Self::spawn(&board, moveable, seed);
+        board[new_index] += new_number;
+
+        self.max_tile = board.iter().copied().fold(self.max_tile, u64::max);
+        if !self.reached && self.max_tile >= self.config.target {
+            self.reached = true;
+            self.reached_at = self.moves;
+        }
+
+        let mut preimage = vec![self.transcript, code];
+        if self.config.transcript_spawns {
+            preimage.push(F::from(new_index as u64));
+            preimage.push(F::from(new_number));
+        }
+        self.transcript = hash(&preimage);
+
+        if counted && self.config.board_history {
+            self.history.append(board);
+        }
+
+        self.board = board;
+        self.game_over = is_game_over(&board);
+    }
+
+    /// Returns the index and the value of the number `GenNext` adds to `board`.
+    fn spawn(board: &[u64; 16], moveable: bool, seed: F) -> (usize, u64) {
+        let candidates: Vec<_> = (0..16).filter(|i| board[*i] == 0).collect();
+        if candidates.is_empty() {
+            return (0, 0);
+        }
+
+        let repr = seed.to_repr();
+        let bytes = repr.as_ref();
+        let n = u128::from_le_bytes(bytes[..NUM_SEED_BITS / 8].try_into().unwrap());
+        let remainder = (bytes[NUM_SEED_BITS / 8] & 1) as u64;
+
+        let new_index = candidates[(n % candidates.len() as u128) as usize];
+        let new_number = if moveable { 2 * (remainder + 1) } else { 0 };
+
+        (new_index, new_number)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+
+    use crate::{circuit::GameConfig, state::GameState};
+
+    use super::{
+        is_game_over, is_start_board, slide, start_board, Direction, Game, MAX_COUNTER, MAX_TILE,
+    };
+
+    #[test]
+    fn test_parse_direction() {
+        assert_eq!("up".parse(), Ok(Direction::Up));
+        assert_eq!("D".parse(), Ok(Direction::Down));
+        assert_eq!("Left".parse(), Ok(Direction::Left));
+        assert_eq!("r".parse(), Ok(Direction::Right));
+        assert_eq!("noop".parse(), Ok(Direction::Noop));
+        assert!("north".parse::<Direction>().is_err());
+
+        for direction in Direction::ALL {
+            assert_eq!(direction.to_string().parse(), Ok(direction));
+        }
+    }
+
+    #[test]
+    fn test_slide() {
+        assert_eq!(slide([2, 2, 2, 2]), ([4, 4, 0, 0], vec![4, 4]));
+        assert_eq!(slide([0, 2, 0, 2]), ([4, 0, 0, 0], vec![4]));
+        assert_eq!(slide([4, 2, 2, 0]), ([4, 4, 0, 0], vec![4]));
+        assert_eq!(slide([2, 2, 4, 4]), ([4, 8, 0, 0], vec![4, 8]));
+        assert_eq!(slide([2, 4, 8, 16]), ([2, 4, 8, 16], vec![]));
+    }
+
+    #[test]
+    fn test_from_state() {
+        let mut game = Game::<Fr>::new([2; 16], GameConfig::default());
+        game.play(Direction::Left);
+        game.play(Direction::Up);
+
+        let resumed = Game::from_state(&game.state(), game.config);
+        assert_eq!(resumed.state(), game.state());
+    }
+
+    #[test]
+    fn test_try_from_z() {
+        let config = GameConfig::default();
+        let mut game = Game::<Fr>::new([2; 16], config);
+        game.play(Direction::Left);
+
+        let z = game.state().to_z();
+        let resumed = Game::try_from_z(&z, config).unwrap();
+        assert_eq!(resumed.state(), game.state());
+
+        assert!(Game::try_from_z(&z[1..], config).is_none());
+
+        let forge = |i: usize, x: Fr| {
+            let mut z = z.clone();
+            z[i] = x;
+            Game::try_from_z(&z, config)
+        };
+        assert!(forge(0, Fr::from(3)).is_none());
+        assert!(forge(0, Fr::from(MAX_TILE * 2)).is_none());
+        assert!(forge(0, -Fr::ONE).is_none());
+        let score = GameState::<Fr>::SUMMARY_INDEX;
+        assert!(forge(score, Fr::from(MAX_COUNTER)).is_none());
+        assert!(forge(score, Fr::from(u64::MAX) + Fr::ONE).is_none());
+        assert!(forge(score, Fr::from(MAX_COUNTER - 1)).is_some());
+    }
+
+    #[test]
+    fn test_start_board() {
+        for nonce in 0..1000 {
+            let board = start_board(nonce);
+            assert_eq!(board.iter().filter(|x| **x == 2).count(), 2);
+            assert!(is_start_board(&board));
+        }
+
+        let mut board = [0; 16];
+        assert!(is_start_board(&board));
+        board[3] = 4;
+        board[7] = 2;
+        assert!(is_start_board(&board));
+        board[7] = 8;
+        assert!(!is_start_board(&board));
+        board[7] = 2;
+        board[9] = 2;
+        assert!(!is_start_board(&board));
+    }
+
+    #[test]
+    fn test_is_game_over() {
+        #[rustfmt::skip]
+        let mut board = [
+            2, 4, 2, 4,
+            4, 2, 4, 2,
+            2, 4, 2, 4,
+            4, 2, 4, 2,
+        ];
+        assert!(is_game_over(&board));
+
+        board[15] = 4;
+        assert!(!is_game_over(&board));
+
+        board[15] = 0;
+        assert!(!is_game_over(&board));
+    }
+}
@@ -0,0 +1,108 @@
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use crate::{state::GameState, NumConstraintSystem};
+
+/// Returns `if_set` if `flag` is 1, `otherwise` if it is 0.
+pub fn select_state<F: PrimeField, CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    flag: &AllocatedNum<F>,
+    if_set: &GameState<AllocatedNum<F>>,
+    otherwise: &GameState<AllocatedNum<F>>,
+) -> Result<GameState<AllocatedNum<F>>, SynthesisError> {
+    let is_set = flag.get_value().map(|x| x == F::ONE).unwrap_or(false);
+
+    let mut z = Vec::new();
+    for (i, (x, y)) in if_set
+        .to_z()
+        .iter()
+        .zip(otherwise.to_z().iter())
+        .enumerate()
+    {
+        let value = if is_set { x.get_value() } else { y.get_value() };
+        let var = AllocatedNum::alloc(cs.namespace(|| format!("alloc_z_{}", i)), || {
+            Ok(value.unwrap_or(F::ZERO))
+        })?;
+
+        cs.enforce(
+            || {
+                format!(
+                    "enforce_(flag * (if_set - otherwise) = z - otherwise)_{}",
+                    i
+                )
+            },
+            |lc| lc + flag.get_variable(),
+            |lc| lc + x.get_variable() - y.get_variable(),
+            |lc| lc + var.get_variable() - y.get_variable(),
+        );
+
+        z.push(var);
+    }
+
+    Ok(GameState::from_z(&z))
+}
+
+/// Ends the game once no move can change the board, and turns every later
+/// move into a no-op.
+///
+/// `GenNext` only knows whether the board is full, but a full board can still
+/// move if two neighbouring tiles are equal. The game is over when there is
+/// no empty cell and no pair of equal neighbours.
+pub struct GameOver<F: PrimeField> {
+    pub previous: GameState<AllocatedNum<F>>,
+
+    pub next: GameState<AllocatedNum<F>>,
+}
+
+impl<F: PrimeField> GameOver<F> {
+    pub fn new(previous: &GameState<AllocatedNum<F>>, next: &GameState<AllocatedNum<F>>) -> Self {
+        Self {
+            previous: previous.clone(),
+            next: next.clone(),
+        }
+    }
+
+    /// Returns `previous` if the game was already over, `next` otherwise,
+    /// with `game_over` updated from the returned board.
+    pub fn synthesize<CS: ConstraintSystem<F>>(
+        &self,
+        mut cs: CS,
+    ) -> Result<GameState<AllocatedNum<F>>, SynthesisError> {
+        let flag = &self.previous.game_over;
+        let is_over = flag.get_value().map(|x| x == F::ONE).unwrap_or(false);
+
+        let mut state = select_state(cs.namespace(|| "select"), flag, &self.previous, &self.next)?;
+
+        // The board can move if it has an empty cell or two equal neighbours.
+        let mut bits = Vec::new();
+        for (i, x) in state.board.iter().enumerate() {
+            bits.push(x.is_equal_to_zero(cs.namespace(|| format!("is_empty_{}", i)))?);
+        }
+        for i in 0..4 {
+            for j in 0..3 {
+                let (a, b) = (&state.board[4 * i + j], &state.board[4 * i + j + 1]);
+                bits.push(a.is_equal(cs.namespace(|| format!("row_{}_{}", i, j)), b)?);
+
+                let (a, b) = (&state.board[4 * j + i], &state.board[4 * (j + 1) + i]);
+                bits.push(a.is_equal(cs.namespace(|| format!("column_{}_{}", i, j)), b)?);
+            }
+        }
+        let sum = AllocatedNum::sum(cs.namespace(|| "sum_of_bits"), &bits)?;
+        let stuck = sum.is_equal_to_zero_bit(cs.namespace(|| "stuck"))?;
+
+        // game_over = flag | stuck = flag + stuck - flag * stuck
+        let value = is_over || stuck.get_value().unwrap_or(false);
+        let game_over = AllocatedNum::alloc(cs.namespace(|| "alloc_game_over"), || {
+            Ok(if value { F::ONE } else { F::ZERO })
+        })?;
+        cs.enforce(
+            || "enforce_(flag * (1 - stuck) = game_over - stuck)",
+            |lc| lc + flag.get_variable(),
+            |lc| lc + CS::one() - stuck.get_variable(),
+            |lc| lc + game_over.get_variable() - stuck.get_variable(),
+        );
+        state.game_over = game_over;
+
+        Ok(state)
+    }
+}
@@ -1,29 +1,64 @@
-use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
-use ff::PrimeField;
+use bellpepper_core::{
+    boolean::Boolean, num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeFieldBits;
 use num_bigint::BigUint;
 use num_integer::Integer;
 
 use crate::NumConstraintSystem;
 
-pub struct GenNext<F: PrimeField> {
+/// The number of low bits of the seed that are reduced modulo the number
+/// of empty cells to pick the position of the new number.
+///
+/// Reducing a 128-bit value modulo at most 16 candidates biases each cell
+/// by less than 2^-124, so every empty cell is chosen with (statistically)
+/// equal probability.
+pub const NUM_SEED_BITS: usize = 128;
+
+pub struct GenNext<F: PrimeFieldBits> {
     pub old_board: Vec<AllocatedNum<F>>,
 
     pub moveable_flag: AllocatedNum<F>,
 
+    /// A uniformly random field element that picks the position and the value
+    /// of the new number. If it is `None`, `n=sum{old_board} + num_candidates`
+    /// is used instead.
+    pub seed: Option<AllocatedNum<F>>,
+
+    /// The number added to the board, 0 if nothing is spawned.
+    pub new_number: Option<AllocatedNum<F>>,
+
+    /// The index (0..16) of the cell that `new_number` is added to.
+    pub new_index: Option<AllocatedNum<F>>,
+
     pub new_board: Vec<AllocatedNum<F>>,
 }
 
-impl<F: PrimeField> GenNext<F> {
+impl<F: PrimeFieldBits> GenNext<F> {
     pub fn new(old_board: &[AllocatedNum<F>], moveable_flag: &AllocatedNum<F>) -> Self {
         assert_eq!(old_board.len(), 16);
 
         Self {
             old_board: old_board.to_vec(),
             moveable_flag: moveable_flag.clone(),
+            seed: None,
+            new_number: None,
+            new_index: None,
             new_board: vec![],
         }
     }
 
+    pub fn with_seed(
+        old_board: &[AllocatedNum<F>],
+        moveable_flag: &AllocatedNum<F>,
+        seed: &AllocatedNum<F>,
+    ) -> Self {
+        Self {
+            seed: Some(seed.clone()),
+            ..Self::new(old_board, moveable_flag)
+        }
+    }
+
     pub fn synthesize<CS: ConstraintSystem<F>>(
         &mut self,
         mut cs: CS,
@@ -40,23 +75,43 @@ impl<F: PrimeField> GenNext<F> {
         let mut num_candidates =
             AllocatedNum::zero(cs.namespace(|| "alloc_zero_for_num_candidates"))?;
 
-        for i in 0..self.old_board.len() {
+        for (i, flipped_bit) in flipped_bits.iter().enumerate() {
             num_candidates = num_candidates.add(
                 cs.namespace(|| format!("num_candidates_add_{}", i)),
-                &flipped_bits[i],
+                flipped_bit,
             )?;
 
             candidates.push(num_candidates.mul(
                 cs.namespace(|| format!("candidates_mul_{}", i)),
-                &flipped_bits[i],
+                flipped_bit,
             )?);
         }
 
-        // A simple calculation for n=sum{old_board} + num_candidates,
+        // `n` picks the position of the new number, and `remainder` picks its value.
         //
-        // todo! replace n=hash(old_board, direction, ...).
-        let mut n = AllocatedNum::sum(cs.namespace(|| "sum_of_board"), &self.old_board)?;
-        n = n.add(cs.namespace(|| "add_num_candidates"), &num_candidates)?;
+        // With a seed, `n` is the low `NUM_SEED_BITS` bits of the seed, and `remainder`
+        // is the bit right above them, so the position and the value are independent.
+        // Without a seed, n=sum{old_board} + num_candidates and remainder = n % 2.
+        let (n, remainder) = match &self.seed {
+            Some(seed) => {
+                let seed_bits = seed.to_bits_le_strict(cs.namespace(|| "seed_to_bits"))?;
+
+                let n = Self::pack_bits(cs.namespace(|| "pack_n"), &seed_bits[..NUM_SEED_BITS])?;
+                let remainder = Self::pack_bits(
+                    cs.namespace(|| "pack_remainder"),
+                    &seed_bits[NUM_SEED_BITS..NUM_SEED_BITS + 1],
+                )?;
+
+                (n, remainder)
+            }
+            None => {
+                let mut n = AllocatedNum::sum(cs.namespace(|| "sum_of_board"), &self.old_board)?;
+                n = n.add(cs.namespace(|| "add_num_candidates"), &num_candidates)?;
+                let remainder = Self::parity(cs.namespace(|| "parity"), &n)?;
+
+                (n, remainder)
+            }
+        };
         let n_bytes = n.get_value().unwrap_or(F::ZERO).to_repr();
         let n_big = BigUint::from_bytes_le(n_bytes.as_ref());
 
@@ -122,62 +177,51 @@ impl<F: PrimeField> GenNext<F> {
             let quotient_var =
                 AllocatedNum::alloc(cs.namespace(|| "alloc_quotient"), || Ok(quotient))?;
 
-            let position = remainder.add(F::ONE);
-            let position_var =
-                AllocatedNum::alloc(cs.namespace(|| "alloc_position"), || Ok(position))?;
+            let remainder_var =
+                AllocatedNum::alloc(cs.namespace(|| "alloc_remainder"), || Ok(remainder))?;
+
+            // `quotient` < 2^NUM_SEED_BITS, `remainder` < 16 and `num_candidates` <= 16,
+            // so the equation below cannot wrap around the modulus, and `remainder` is
+            // exactly n % num_candidates.
+            quotient_var.to_bits(cs.namespace(|| "quotient_to_bits"), NUM_SEED_BITS)?;
+            remainder_var.to_bits(cs.namespace(|| "remainder_to_bits"), 4)?;
 
             cs.enforce(
-                || "enforce_(quotient * num_candidates = n + 1 - position)",
+                || "enforce_(quotient * num_candidates = n - remainder)",
                 |lc| lc + quotient_var.get_variable(),
                 |lc| lc + num_candidates.get_variable(),
-                |lc| lc + n.get_variable() + CS::one() - position_var.get_variable(),
+                |lc| lc + n.get_variable() - remainder_var.get_variable(),
             );
 
-            position_var
-        };
+            let position = remainder.add(F::ONE);
+            let position_var =
+                AllocatedNum::alloc(cs.namespace(|| "alloc_position"), || Ok(position))?;
 
-        let new_number = {
-            let m = F::from(2);
-            let m_big = BigUint::from_bytes_le(m.to_repr().as_ref());
-            let m_var = AllocatedNum::alloc(cs.namespace(|| "alloc_m"), || Ok(m))?;
             cs.enforce(
-                || "enforce_(m = 2)",
-                |lc| lc,
-                |lc| lc,
-                |lc| lc + m_var.get_variable() - CS::one() - CS::one(),
+                || "enforce_(position = remainder + 1)",
+                |lc| lc + remainder_var.get_variable() + CS::one(),
+                |lc| lc + CS::one(),
+                |lc| lc + position_var.get_variable(),
             );
 
-            let (quotient, remainder) = n_big.div_rem(&m_big);
-            let quotient = F::from_str_vartime(&quotient.to_string()).unwrap();
-            let remainder = F::from_str_vartime(&remainder.to_string()).unwrap();
-            let quotient_var =
-                AllocatedNum::alloc(cs.namespace(|| "alloc_quotient_for_new"), || Ok(quotient))?;
-            let remainder_var =
-                AllocatedNum::alloc(cs.namespace(|| "alloc_remainder"), || Ok(remainder))?;
-
-            cs.enforce(
-                || "enfore_(remainder < 2)",
-                |lc| lc + remainder_var.get_variable(),
-                |lc| lc + remainder_var.get_variable() - CS::one(),
-                |lc| lc,
-            );
-            cs.enforce(
-                || "enfore_(m * quotient = n - remainder)",
-                |lc| lc + m_var.get_variable(),
-                |lc| lc + quotient_var.get_variable(),
-                |lc| lc + n.get_variable() - remainder_var.get_variable(),
-            );
+            position_var
+        };
 
+        let new_number = {
             // When the `remainder` is equal to 0, `new_number` is set to 2.
             // When the `remainder` is equal to 1, `new_number` is set to 4.
             //
             // So the new_number = 2 * (remainder +1).
-            let new_number = remainder.add(F::ONE).mul(F::from(2));
+            let new_number = remainder
+                .get_value()
+                .unwrap_or(F::ZERO)
+                .add(F::ONE)
+                .mul(F::from(2));
             let mut new_number_var =
                 AllocatedNum::alloc(cs.namespace(|| "alloc_new_number"), || Ok(new_number))?;
             cs.enforce(
                 || "enfore_(2 * (remainder +1))",
-                |lc| lc + remainder_var.get_variable() + CS::one(),
+                |lc| lc + remainder.get_variable() + CS::one(),
                 |lc| lc + CS::one() + CS::one(),
                 |lc| lc + new_number_var.get_variable(),
             );
@@ -216,6 +260,32 @@ impl<F: PrimeField> GenNext<F> {
             |lc| lc + sum_bits.get_variable() - CS::one(),
         );
 
+        let new_index = {
+            let mut index = F::ZERO;
+            for (i, bit) in bits.iter().enumerate() {
+                index += F::from(i as u64) * bit.get_value().unwrap_or(F::ZERO);
+            }
+            let index_var = AllocatedNum::alloc(cs.namespace(|| "alloc_new_index"), || Ok(index))?;
+
+            let linear_combination = |init: LinearCombination<F>| -> LinearCombination<F> {
+                let mut sum = init;
+                for (i, bit) in bits.iter().enumerate() {
+                    sum = sum + (F::from(i as u64), bit.get_variable());
+                }
+
+                sum
+            };
+
+            cs.enforce(
+                || "enforce_(new_index = sum{i * bits[i]})",
+                linear_combination,
+                |lc| lc + CS::one(),
+                |lc| lc + index_var.get_variable(),
+            );
+
+            index_var
+        };
+
         let mut new_number_mul_bits = Vec::new();
         for (i, bit) in bits.iter().enumerate() {
             let r = new_number.mul(cs.namespace(|| format!("new_number_mul_bit_{}", i)), bit)?;
@@ -235,10 +305,95 @@ impl<F: PrimeField> GenNext<F> {
             new_board.push(r);
         }
 
+        self.new_number = Some(new_number);
+        self.new_index = Some(new_index);
         self.new_board = new_board;
 
         Ok(())
     }
+
+    /// Returns a variable that equals the little-endian integer of `bits`.
+    fn pack_bits<CS: ConstraintSystem<F>>(
+        mut cs: CS,
+        bits: &[Boolean],
+    ) -> Result<AllocatedNum<F>, SynthesisError> {
+        let mut value = F::ZERO;
+        let mut coeff = F::ONE;
+        for bit in bits.iter() {
+            if bit.get_value().unwrap_or(false) {
+                value += coeff;
+            }
+            coeff = coeff.double();
+        }
+
+        let packed = AllocatedNum::alloc(cs.namespace(|| "alloc_packed"), || Ok(value))?;
+
+        let linear_combination = |init: LinearCombination<F>| -> LinearCombination<F> {
+            let mut sum = init;
+            let mut coeff = F::ONE;
+            for bit in bits.iter() {
+                sum = sum + &bit.lc(CS::one(), coeff);
+                coeff = coeff.double();
+            }
+
+            sum
+        };
+
+        cs.enforce(
+            || "enforce_(sum of bits = packed)",
+            linear_combination,
+            |lc| lc + CS::one(),
+            |lc| lc + packed.get_variable(),
+        );
+
+        Ok(packed)
+    }
+
+    /// Returns `n` % 2 for an `n` < 2^NUM_SEED_BITS.
+    fn parity<CS: ConstraintSystem<F>>(
+        mut cs: CS,
+        n: &AllocatedNum<F>,
+    ) -> Result<AllocatedNum<F>, SynthesisError> {
+        let m = F::from(2);
+        let m_big = BigUint::from_bytes_le(m.to_repr().as_ref());
+        let m_var = AllocatedNum::alloc(cs.namespace(|| "alloc_m"), || Ok(m))?;
+        cs.enforce(
+            || "enforce_(m = 2)",
+            |lc| lc,
+            |lc| lc,
+            |lc| lc + m_var.get_variable() - CS::one() - CS::one(),
+        );
+
+        let n_bytes = n.get_value().unwrap_or(F::ZERO).to_repr();
+        let n_big = BigUint::from_bytes_le(n_bytes.as_ref());
+
+        let (quotient, remainder) = n_big.div_rem(&m_big);
+        let quotient = F::from_str_vartime(&quotient.to_string()).unwrap();
+        let remainder = F::from_str_vartime(&remainder.to_string()).unwrap();
+        let quotient_var =
+            AllocatedNum::alloc(cs.namespace(|| "alloc_quotient_for_new"), || Ok(quotient))?;
+        let remainder_var =
+            AllocatedNum::alloc(cs.namespace(|| "alloc_remainder"), || Ok(remainder))?;
+
+        // Without this range check, both remainders would satisfy the equation
+        // below for a suitable `quotient`.
+        quotient_var.to_bits(cs.namespace(|| "quotient_to_bits"), NUM_SEED_BITS)?;
+
+        cs.enforce(
+            || "enfore_(remainder < 2)",
+            |lc| lc + remainder_var.get_variable(),
+            |lc| lc + remainder_var.get_variable() - CS::one(),
+            |lc| lc,
+        );
+        cs.enforce(
+            || "enfore_(m * quotient = n - remainder)",
+            |lc| lc + m_var.get_variable(),
+            |lc| lc + quotient_var.get_variable(),
+            |lc| lc + n.get_variable() - remainder_var.get_variable(),
+        );
+
+        Ok(remainder_var)
+    }
 }
 
 #[cfg(test)]
@@ -246,6 +401,7 @@ mod test {
     use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
     use blstrs::Scalar as Fr;
     use ff::Field;
+    use rand::{rngs::StdRng, SeedableRng};
 
     use super::GenNext;
 
@@ -336,4 +492,68 @@ mod test {
             board
         );
     }
+
+    #[test]
+    fn test_seed_distribution() {
+        let zero = Fr::ZERO;
+        let one = Fr::ONE;
+        let two = Fr::from(2);
+        let four = Fr::from(4);
+        let eight = Fr::from(8);
+
+        // Three empty cells at 1, 6 and 11.
+        #[rustfmt::skip]
+        let board = vec![
+            two,   zero,   two,   four,
+            two,   eight,  zero,  eight,
+            four,  eight,  two,   zero,
+            two,   four,   eight, two,
+        ];
+        let empty_cells = [1, 6, 11];
+
+        let num_samples = 300;
+        let mut position_counts = [0; 3];
+        let mut four_count = 0;
+
+        let mut rng = StdRng::seed_from_u64(2048);
+        for _ in 0..num_samples {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let mut board_vars = Vec::new();
+            for (i, x) in board.iter().enumerate() {
+                board_vars.push(
+                    AllocatedNum::alloc(cs.namespace(|| format!("board_{i}")), || Ok(*x)).unwrap(),
+                );
+            }
+
+            let moveable = AllocatedNum::alloc(cs.namespace(|| "moveable"), || Ok(one)).unwrap();
+            let seed =
+                AllocatedNum::alloc(cs.namespace(|| "seed"), || Ok(Fr::random(&mut rng))).unwrap();
+
+            let mut circuit = GenNext::with_seed(&board_vars, &moveable, &seed);
+            circuit.synthesize(&mut cs).unwrap();
+            assert!(cs.is_satisfied());
+
+            let new_board: Vec<_> = circuit
+                .new_board
+                .iter()
+                .map(|x| x.get_value().unwrap())
+                .collect();
+            let changed: Vec<_> = (0..16).filter(|i| new_board[*i] != board[*i]).collect();
+            assert_eq!(changed.len(), 1);
+
+            let position = empty_cells.iter().position(|x| *x == changed[0]).unwrap();
+            position_counts[position] += 1;
+            if new_board[changed[0]] == four {
+                four_count += 1;
+            }
+        }
+
+        // Each cell is expected 100 times, the standard deviation is about 8.2.
+        for count in position_counts {
+            assert!((70..=130).contains(&count), "{:?}", position_counts);
+        }
+        // The value is expected to be 4 150 times, the standard deviation is about 8.7.
+        assert!((115..=185).contains(&four_count), "{}", four_count);
+    }
 }
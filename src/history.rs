@@ -0,0 +1,418 @@
+use std::collections::HashMap;
+
+use bellpepper_core::{boolean::AllocatedBit, num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use crate::{
+    poseidon::{hash, hash_circuit},
+    NumConstraintSystem,
+};
+
+/// The depth of the Merkle tree of the board history, which holds up to
+/// 2^HISTORY_DEPTH boards.
+pub const HISTORY_DEPTH: usize = 20;
+
+/// Returns the leaf of a board, hash(board).
+pub fn board_leaf<F: PrimeField>(board: &[u64; 16]) -> F {
+    hash(&board.map(F::from))
+}
+
+/// Returns the roots of the empty trees of depth 0 to HISTORY_DEPTH, where an
+/// empty leaf is 0.
+fn empty_roots<F: PrimeField>() -> Vec<F> {
+    let mut roots = vec![F::ZERO];
+    for i in 0..HISTORY_DEPTH {
+        roots.push(hash(&[roots[i], roots[i]]));
+    }
+
+    roots
+}
+
+/// Returns the root of a tree from a leaf and its path, see `BoardHistory::path`.
+fn root_of<F: PrimeField>(index: u64, leaf: F, path: &[F]) -> F {
+    assert_eq!(path.len(), HISTORY_DEPTH);
+
+    let mut node = leaf;
+    for (i, sibling) in path.iter().enumerate() {
+        node = if (index >> i) & 1 == 1 {
+            hash(&[*sibling, node])
+        } else {
+            hash(&[node, *sibling])
+        };
+    }
+
+    node
+}
+
+/// The boards of a game in an append-only Merkle tree, out of the circuit.
+///
+/// Leaf k is the board after move k + 1, see `GameConfig::board_history`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardHistory<F: PrimeField> {
+    pub boards: Vec<[u64; 16]>,
+
+    /// The nodes of the tree, leaves first. Missing nodes are empty.
+    levels: Vec<Vec<F>>,
+
+    empty: Vec<F>,
+}
+
+impl<F: PrimeField> Default for BoardHistory<F> {
+    fn default() -> Self {
+        Self {
+            boards: vec![],
+            levels: vec![vec![]; HISTORY_DEPTH + 1],
+            empty: empty_roots(),
+        }
+    }
+}
+
+impl<F: PrimeField> BoardHistory<F> {
+    /// Returns the root of the empty history.
+    pub fn empty_root() -> F {
+        empty_roots::<F>()[HISTORY_DEPTH]
+    }
+
+    pub fn root(&self) -> F {
+        self.node(HISTORY_DEPTH, 0)
+    }
+
+    fn node(&self, level: usize, index: usize) -> F {
+        self.levels[level]
+            .get(index)
+            .copied()
+            .unwrap_or(self.empty[level])
+    }
+
+    /// Returns the siblings of leaf `index`, from the bottom up.
+    pub fn path(&self, index: u64) -> Vec<F> {
+        (0..HISTORY_DEPTH)
+            .map(|level| self.node(level, ((index >> level) ^ 1) as usize))
+            .collect()
+    }
+
+    /// Returns the path which `AppendBoard` needs to append the next board.
+    pub fn next_path(&self) -> Vec<F> {
+        self.path(self.boards.len() as u64)
+    }
+
+    /// Returns the paths which `AppendBoard` needs for a run of moves, where
+    /// `appends[i]` is the board that move i appends, if it is counted, without
+    /// appending them: only the nodes the appends change are computed.
+    pub fn next_paths(&self, appends: &[Option<[u64; 16]>]) -> Vec<Vec<F>> {
+        let mut changed = HashMap::new();
+        let node = |changed: &HashMap<(usize, usize), F>, level: usize, index: usize| {
+            changed
+                .get(&(level, index))
+                .copied()
+                .unwrap_or_else(|| self.node(level, index))
+        };
+
+        let mut len = self.boards.len();
+        let mut paths = vec![];
+        for append in appends {
+            paths.push(
+                (0..HISTORY_DEPTH)
+                    .map(|level| node(&changed, level, (len >> level) ^ 1))
+                    .collect(),
+            );
+
+            if let Some(board) = append {
+                assert!(len < 1 << HISTORY_DEPTH, "the history is full");
+
+                let mut index = len;
+                let mut value = board_leaf(board);
+                for level in 0..HISTORY_DEPTH {
+                    changed.insert((level, index), value);
+                    let sibling = node(&changed, level, index ^ 1);
+                    value = if index & 1 == 1 {
+                        hash(&[sibling, value])
+                    } else {
+                        hash(&[value, sibling])
+                    };
+                    index >>= 1;
+                }
+                len += 1;
+            }
+        }
+
+        paths
+    }
+
+    pub fn append(&mut self, board: [u64; 16]) {
+        assert!(
+            self.boards.len() < 1 << HISTORY_DEPTH,
+            "the history is full"
+        );
+
+        let mut index = self.boards.len();
+        let mut node = board_leaf(&board);
+        self.boards.push(board);
+
+        for level in 0..=HISTORY_DEPTH {
+            if index < self.levels[level].len() {
+                self.levels[level][index] = node;
+            } else {
+                self.levels[level].push(node);
+            }
+
+            if level < HISTORY_DEPTH {
+                node = if index & 1 == 1 {
+                    hash(&[self.node(level, index - 1), node])
+                } else {
+                    hash(&[node, self.node(level, index + 1)])
+                };
+                index >>= 1;
+            }
+        }
+    }
+
+    /// Returns an opening of the board after move `k`, counting from 1, or
+    /// `None` if there is no such move.
+    pub fn open(&self, k: u64) -> Option<HistoryOpening<F>> {
+        let index = k.checked_sub(1)?;
+        let board = *self.boards.get(index as usize)?;
+
+        Some(HistoryOpening {
+            index,
+            board,
+            path: self.path(index),
+        })
+    }
+}
+
+/// Shows that a history root holds a board at a given index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryOpening<F: PrimeField> {
+    /// The index of the leaf, the move counted from 0.
+    pub index: u64,
+
+    pub board: [u64; 16],
+
+    pub path: Vec<F>,
+}
+
+impl<F: PrimeField> HistoryOpening<F> {
+    /// Returns true if `root` holds `board` at `index`.
+    pub fn verify(&self, root: F) -> bool {
+        self.path.len() == HISTORY_DEPTH
+            && self.index < 1 << HISTORY_DEPTH
+            && root_of(self.index, board_leaf(&self.board), &self.path) == root
+    }
+}
+
+/// Appends a board to the history in the circuit.
+///
+/// If `append` is 1, the leaf at `index` must be empty under `root`, which
+/// `path` shows, and the new root puts the leaf of `board` there. Otherwise
+/// the root is kept and neither `index` nor `path` is checked, so moves which
+/// do not append still fit once `index` is past the tree.
+pub struct AppendBoard<F: PrimeField> {
+    pub root: AllocatedNum<F>,
+
+    pub index: AllocatedNum<F>,
+
+    pub append: AllocatedNum<F>,
+
+    pub board: Vec<AllocatedNum<F>>,
+
+    pub path: Vec<F>,
+}
+
+impl<F: PrimeField> AppendBoard<F> {
+    pub fn new(
+        root: &AllocatedNum<F>,
+        index: &AllocatedNum<F>,
+        append: &AllocatedNum<F>,
+        board: &[AllocatedNum<F>],
+        path: &[F],
+    ) -> Self {
+        assert_eq!(board.len(), 16);
+        assert_eq!(path.len(), HISTORY_DEPTH);
+
+        Self {
+            root: root.clone(),
+            index: index.clone(),
+            append: append.clone(),
+            board: board.to_vec(),
+            path: path.to_vec(),
+        }
+    }
+
+    /// Returns the root of the tree from `leaf` up the path.
+    fn root_of<CS: ConstraintSystem<F>>(
+        mut cs: CS,
+        leaf: &AllocatedNum<F>,
+        bits: &[AllocatedBit],
+        path: &[AllocatedNum<F>],
+    ) -> Result<AllocatedNum<F>, SynthesisError> {
+        let mut node = leaf.clone();
+        for (i, (bit, sibling)) in bits.iter().zip(path.iter()).enumerate() {
+            // left = node + bit * (sibling - node), right = node + sibling - left
+            let is_right = bit.get_value().unwrap_or(false);
+            let (node_value, sibling_value) = (
+                node.get_value().unwrap_or(F::ZERO),
+                sibling.get_value().unwrap_or(F::ZERO),
+            );
+            let (left_value, right_value) = if is_right {
+                (sibling_value, node_value)
+            } else {
+                (node_value, sibling_value)
+            };
+
+            let left = AllocatedNum::alloc(cs.namespace(|| format!("alloc_left_{}", i)), || {
+                Ok(left_value)
+            })?;
+            let right = AllocatedNum::alloc(cs.namespace(|| format!("alloc_right_{}", i)), || {
+                Ok(right_value)
+            })?;
+
+            cs.enforce(
+                || format!("enforce_(bit * (sibling - node) = left - node)_{}", i),
+                |lc| lc + bit.get_variable(),
+                |lc| lc + sibling.get_variable() - node.get_variable(),
+                |lc| lc + left.get_variable() - node.get_variable(),
+            );
+            cs.enforce(
+                || format!("enforce_(left + right = node + sibling)_{}", i),
+                |lc| lc + left.get_variable() + right.get_variable(),
+                |lc| lc + CS::one(),
+                |lc| lc + node.get_variable() + sibling.get_variable(),
+            );
+
+            node = hash_circuit(cs.namespace(|| format!("hash_{}", i)), &[left, right])?;
+        }
+
+        Ok(node)
+    }
+
+    /// Returns the new root.
+    pub fn synthesize<CS: ConstraintSystem<F>>(
+        &self,
+        mut cs: CS,
+    ) -> Result<AllocatedNum<F>, SynthesisError> {
+        // The index is only range-checked when appending.
+        let index = self
+            .append
+            .mul(cs.namespace(|| "append_index"), &self.index)?;
+        let bits = index.to_bits(cs.namespace(|| "index_bits"), HISTORY_DEPTH)?;
+
+        let mut path = Vec::new();
+        for (i, x) in self.path.iter().enumerate() {
+            path.push(AllocatedNum::alloc(
+                cs.namespace(|| format!("alloc_sibling_{}", i)),
+                || Ok(*x),
+            )?);
+        }
+
+        let empty_leaf = AllocatedNum::zero(cs.namespace(|| "empty_leaf"))?;
+        let old_root = Self::root_of(cs.namespace(|| "old_root"), &empty_leaf, &bits, &path)?;
+        cs.enforce(
+            || "enforce_(append * (old_root - root) = 0)",
+            |lc| lc + self.append.get_variable(),
+            |lc| lc + old_root.get_variable() - self.root.get_variable(),
+            |lc| lc,
+        );
+
+        let leaf = hash_circuit(cs.namespace(|| "leaf"), &self.board)?;
+        let new_root = Self::root_of(cs.namespace(|| "new_root"), &leaf, &bits, &path)?;
+
+        // root = old_root + append * (new_root - old_root)
+        let value = if self.append.get_value() == Some(F::ONE) {
+            new_root.get_value()
+        } else {
+            self.root.get_value()
+        };
+        let root = AllocatedNum::alloc(cs.namespace(|| "alloc_root"), || {
+            Ok(value.unwrap_or(F::ZERO))
+        })?;
+        cs.enforce(
+            || "enforce_(append * (new_root - old_root) = root - old_root)",
+            |lc| lc + self.append.get_variable(),
+            |lc| lc + new_root.get_variable() - self.root.get_variable(),
+            |lc| lc + root.get_variable() - self.root.get_variable(),
+        );
+
+        Ok(root)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+    use blstrs::Scalar as Fr;
+
+    use super::{AppendBoard, BoardHistory, HISTORY_DEPTH};
+
+    #[test]
+    fn test_board_history() {
+        let mut history = BoardHistory::<Fr>::default();
+        assert_eq!(history.root(), BoardHistory::<Fr>::empty_root());
+
+        let boards: Vec<[u64; 16]> = (0..5).map(|i| [2 << i; 16]).collect();
+        let mut roots = vec![history.root()];
+        for board in boards.iter() {
+            history.append(*board);
+            roots.push(history.root());
+        }
+
+        for k in 1..=5 {
+            let opening = history.open(k).unwrap();
+            assert_eq!(opening.board, boards[k as usize - 1]);
+            assert!(opening.verify(history.root()));
+            assert!(!opening.verify(roots[k as usize - 1]));
+
+            let mut forged = opening.clone();
+            forged.board[0] = 4096;
+            assert!(!forged.verify(history.root()));
+        }
+
+        assert!(history.open(0).is_none());
+        assert!(history.open(6).is_none());
+
+        // The paths of appends ahead of time are the paths of the appends.
+        let appends = [Some([2; 16]), None, Some([4; 16]), Some([8; 16]), None];
+        let paths = history.next_paths(&appends);
+        for (append, path) in appends.iter().zip(paths) {
+            assert_eq!(path, history.next_path());
+            if let Some(board) = append {
+                history.append(*board);
+            }
+        }
+    }
+
+    #[test]
+    fn test_append_board() {
+        let history = BoardHistory::<Fr>::default();
+        let board = [2; 16];
+        let path = history.next_path();
+
+        // Returns whether appending at `index` is satisfiable, and the root.
+        let append_at = |index: u64, append: bool| {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let mut alloc = |name: &str, value: Fr| {
+                AllocatedNum::alloc(cs.namespace(|| name.to_string()), || Ok(value)).unwrap()
+            };
+            let root = alloc("root", history.root());
+            let index = alloc("index", Fr::from(index));
+            let append = alloc("append", Fr::from(append as u64));
+            let board: Vec<_> = (0..16)
+                .map(|i| alloc(&format!("board_{}", i), Fr::from(board[i])))
+                .collect();
+
+            let new_root = AppendBoard::new(&root, &index, &append, &board, &path)
+                .synthesize(cs.namespace(|| "append_board"))
+                .unwrap();
+            (cs.is_satisfied(), new_root.get_value().unwrap())
+        };
+
+        let mut appended = history.clone();
+        appended.append(board);
+        assert_eq!(append_at(0, true), (true, appended.root()));
+        assert_eq!(append_at(0, false), (true, history.root()));
+
+        // Past the tree, only moves which do not append fit.
+        assert!(!append_at(1 << HISTORY_DEPTH, true).0);
+        assert_eq!(append_at(1 << HISTORY_DEPTH, false), (true, history.root()));
+    }
+}
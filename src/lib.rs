@@ -3,12 +3,32 @@ use bellpepper_core::{
 };
 use ff::PrimeField;
 
+pub mod background;
+pub mod bundle;
 pub mod can_move;
+pub mod checkpoint;
+pub mod circuit;
+pub mod claim;
+pub mod commitment;
 pub mod direction_chooser;
+pub mod game;
+pub mod game_over;
 pub mod gen_next;
+pub mod history;
+pub mod max_tile;
 pub mod merge;
+pub mod merge_counter;
+pub mod packing;
+pub mod params;
+pub mod poseidon;
+pub mod prover;
 pub mod restore;
+pub mod session;
 pub mod sort;
+pub mod state;
+pub mod tile_sum;
+pub mod transcript;
+pub mod verifier;
 
 pub trait NumConstraintSystem<F: PrimeField> {
     type Output: NumConstraintSystem<F>;
@@ -31,7 +51,7 @@ pub trait NumConstraintSystem<F: PrimeField> {
         mut cs: CS,
     ) -> Result<Self::Output, SynthesisError> {
         let zero = Self::zero(cs.namespace(|| "zero"))?;
-        let bit = Self::is_equal(&self, cs.namespace(|| "is_equal_to_zero"), &zero)?;
+        let bit = Self::is_equal(self, cs.namespace(|| "is_equal_to_zero"), &zero)?;
 
         Ok(bit)
     }
@@ -60,6 +80,14 @@ pub trait NumConstraintSystem<F: PrimeField> {
     /// Apply a boolean constraint.
     fn apply_bool_constraint<CS: ConstraintSystem<F>>(&self, cs: CS);
 
+    /// Decomposes `self` into `num_bits` little-endian bits, enforcing
+    /// that `self` < 2^num_bits.
+    fn to_bits<CS: ConstraintSystem<F>>(
+        &self,
+        cs: CS,
+        num_bits: usize,
+    ) -> Result<Vec<AllocatedBit>, SynthesisError>;
+
     /// Sums up a collection of variables.
     fn sum<CS: ConstraintSystem<F>>(
         cs: CS,
@@ -146,7 +174,7 @@ impl<F: PrimeField> NumConstraintSystem<F> for AllocatedNum<F> {
             .unwrap_or(F::ZERO);
         let inv_var = AllocatedNum::alloc(cs.namespace(|| "alloc_inv"), || Ok(inv))?;
 
-        let bit = if val.is_zero().into() { true } else { false };
+        let bit = val.is_zero().into();
         let bit_var = AllocatedBit::alloc(cs.namespace(|| "alloc_bit"), Some(bit))?;
 
         cs.enforce(
@@ -228,6 +256,46 @@ impl<F: PrimeField> NumConstraintSystem<F> for AllocatedNum<F> {
         );
     }
 
+    fn to_bits<CS: ConstraintSystem<F>>(
+        &self,
+        mut cs: CS,
+        num_bits: usize,
+    ) -> Result<Vec<AllocatedBit>, SynthesisError> {
+        assert!(num_bits as u32 <= F::CAPACITY);
+
+        let repr = self.get_value().unwrap_or(F::ZERO).to_repr();
+        let bytes = repr.as_ref();
+
+        let mut bits = Vec::new();
+        for i in 0..num_bits {
+            let bit = (bytes[i / 8] >> (i % 8)) & 1 == 1;
+            bits.push(AllocatedBit::alloc(
+                cs.namespace(|| format!("alloc_bit_{}", i)),
+                Some(bit),
+            )?);
+        }
+
+        let linear_combination = |init: LinearCombination<F>| -> LinearCombination<F> {
+            let mut sum = init;
+            let mut coeff = F::ONE;
+            for bit in bits.iter() {
+                sum = sum + (coeff, bit.get_variable());
+                coeff = coeff.double();
+            }
+
+            sum
+        };
+
+        cs.enforce(
+            || "enforce_(sum of bits = self)",
+            linear_combination,
+            |lc| lc + CS::one(),
+            |lc| lc + self.get_variable(),
+        );
+
+        Ok(bits)
+    }
+
     fn sum<CS: ConstraintSystem<F>>(
         mut cs: CS,
         vars: &[Self::Output],
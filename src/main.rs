@@ -0,0 +1,801 @@
+//! The command line of nova-2048, see `USAGE`.
+
+use std::{
+    env, fmt, fs,
+    io::{self, Write},
+    path::PathBuf,
+    process,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ff::{Field, PrimeField};
+use nova_2048::{
+    background::BackgroundProver,
+    bundle::{decode_header, ProofBundle},
+    checkpoint::{load_checkpoint, save_checkpoint},
+    circuit::GameConfig,
+    game::{is_start_board, player_id, start_board, to_u64, Direction, Game},
+    params::{load_prover_key, save_prover_key, save_verifier_key, ParamsKind},
+    prover::{GameProof, GameProver, GameProverKey},
+    session::Session,
+    verifier::{verify_game, GameVerifier, RevealedState},
+};
+use nova_snark::traits::Group;
+
+type E1 = pasta_curves::pallas::Point;
+type E2 = pasta_curves::vesta::Point;
+type F1 = <E1 as Group>::Scalar;
+type EE1 = nova_snark::provider::ipa_pc::EvaluationEngine<E1>;
+type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
+type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>;
+type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>;
+
+const USAGE: &str = "\
+Usage: nova-2048 <command> [options]
+
+Commands:
+  setup    Generate the public parameters and the keys, and save them
+  prove    Prove a game and write its proof bundle
+  verify   Verify a proof bundle and print the final state
+  replay   Print the board after every move of a game
+  play     Play a game in the terminal, then prove it
+
+Options:
+  --params <dir>          Directory of the parameters and keys [default: params]
+  --moves-per-step <n>    Moves per step of the circuit [default: 10]
+  --board <file>          Starting board: 16 numbers, row by row, with at
+                          most two tiles, each a 2 or a 4, for prove and play
+                          [default: the # board: line of the moves file, or
+                          for play two 2s placed from the nonce]
+  --moves <file>          Moves: up, down, left, right (or u, d, l, r), and
+                          lines starting with # are comments [default for
+                          play, which writes it: moves.txt]
+  --overwrite             Let play replace an existing moves file
+  --proof <file>          Proof bundle [default: proof.bin]
+  --player <name>         Player of the game [default: the # player: line
+                          of the moves file]
+  --nonce <n>             Nonce of the game session [default: the # nonce:
+                          line of the moves file, or 0, or the current time
+                          for play]
+  --checkpoint <file>     Save the proof after every step of prove, and
+                          resume from there if the file exists
+
+Rules (setup and prove only, verify reads them from the bundle):
+  --target <n>            Tile to reach [default: 2048]
+  --tile-sum              Check the sum of the tiles after every move, to
+                          catch bugs in the circuit
+  --transcript-spawns     Absorb the spawned numbers into the transcript
+  --count-moved-only      Only count the moves which change the board
+  --packed-board          Pack the board into one element of z
+  --board-history         Keep a Merkle history of the boards
+";
+
+struct Options {
+    params: PathBuf,
+    moves_per_step: usize,
+    config: GameConfig,
+    board: Option<PathBuf>,
+    moves: Option<PathBuf>,
+    overwrite: bool,
+    proof: PathBuf,
+    player: Option<String>,
+    nonce: Option<u64>,
+    checkpoint: Option<PathBuf>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Options {
+            params: PathBuf::from("params"),
+            moves_per_step: 10,
+            config: GameConfig::default(),
+            board: None,
+            moves: None,
+            overwrite: false,
+            proof: PathBuf::from("proof.bin"),
+            player: None,
+            nonce: None,
+            checkpoint: None,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("{} needs a value", arg))
+            };
+            let number = |value: String| {
+                value
+                    .parse::<u64>()
+                    .map_err(|_| format!("{} needs a number, not {:?}", arg, value))
+            };
+
+            match arg.as_str() {
+                "--params" => options.params = value()?.into(),
+                "--moves-per-step" => options.moves_per_step = number(value()?)? as usize,
+                "--board" => options.board = Some(value()?.into()),
+                "--moves" => options.moves = Some(value()?.into()),
+                "--overwrite" => options.overwrite = true,
+                "--proof" => options.proof = value()?.into(),
+                "--player" => options.player = Some(value()?),
+                "--nonce" => options.nonce = Some(number(value()?)?),
+                "--checkpoint" => options.checkpoint = Some(value()?.into()),
+                "--target" => options.config.target = number(value()?)?,
+                "--tile-sum" => options.config.check_tile_sum = true,
+                "--transcript-spawns" => options.config.transcript_spawns = true,
+                "--count-moved-only" => options.config.count_stuck_moves = false,
+                "--packed-board" => options.config.packed_board = true,
+                "--board-history" => options.config.board_history = true,
+                _ => return Err(format!("unknown option {}", arg)),
+            }
+        }
+
+        if options.moves_per_step == 0 {
+            return Err("--moves-per-step must be at least 1".to_string());
+        }
+
+        Ok(options)
+    }
+
+    /// Returns the game of `--board`, `--player` and `--nonce`, or of the
+    /// header of `--moves` for those which are missing, see `MovesHeader`.
+    fn game(&self) -> Result<Game<F1>, String> {
+        let header = match &self.moves {
+            Some(path) => parse_header(&read_to_string(path)?)
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+            None => MovesHeader::default(),
+        };
+        let board = match (&self.board, header.board) {
+            (Some(path), _) => read_board(path)?,
+            (None, Some(board)) => board,
+            (None, None) => return Err("--board is missing".to_string()),
+        };
+        let nonce = self.nonce.or(header.nonce).unwrap_or(0);
+        let player = self.player.clone().or(header.player);
+
+        Ok(self.game_from(board, nonce, player.as_deref()))
+    }
+
+    fn game_from(&self, board: [u64; 16], nonce: u64, player: Option<&str>) -> Game<F1> {
+        let mut game = Game::new(board, self.config).with_nonce(F1::from(nonce));
+        if let Some(player) = player {
+            game = game.with_player(player_id(player.as_bytes()));
+        }
+
+        game
+    }
+
+    /// Returns the moves of `--moves`.
+    fn moves(&self) -> Result<Vec<Direction>, String> {
+        let path = self.moves.as_ref().ok_or("--moves is missing")?;
+        let text = read_to_string(path)?;
+
+        parse_moves(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn prover(&self) -> Result<GameProver<E1, E2>, String> {
+        GameProver::load_or_setup(&self.params, self.config, self.moves_per_step)
+            .map_err(|e| format!("{}: {}", self.params.display(), e))
+    }
+}
+
+fn read_to_string(path: &PathBuf) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// The start of the game of a moves file, in its `# board:`, `# nonce:` and
+/// `# player:` comments, which `play` writes so that `prove` and `replay` do
+/// not need the other options.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct MovesHeader {
+    board: Option<[u64; 16]>,
+    nonce: Option<u64>,
+    player: Option<String>,
+}
+
+impl fmt::Display for MovesHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(board) = &self.board {
+            let cells: Vec<_> = board.iter().map(|x| x.to_string()).collect();
+            writeln!(f, "# board: {}", cells.join(" "))?;
+        }
+        if let Some(nonce) = self.nonce {
+            writeln!(f, "# nonce: {}", nonce)?;
+        }
+        if let Some(player) = &self.player {
+            writeln!(f, "# player: {}", player)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the header of a moves file, see `MovesHeader`. Other comments are
+/// skipped.
+fn parse_header(text: &str) -> Result<MovesHeader, String> {
+    let mut header = MovesHeader::default();
+    for line in text.lines() {
+        let Some(comment) = line.trim_start().strip_prefix('#') else {
+            continue;
+        };
+        let Some((name, value)) = comment.split_once(':') else {
+            continue;
+        };
+
+        let value = value.trim();
+        match name.trim() {
+            "board" => header.board = Some(parse_board(value)?),
+            "nonce" => {
+                let nonce = value
+                    .parse()
+                    .map_err(|_| format!("the nonce must be a number, not {:?}", value))?;
+                header.nonce = Some(nonce);
+            }
+            "player" => header.player = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    Ok(header)
+}
+
+/// Parses moves separated by whitespace or commas, skipping the lines
+/// starting with #.
+fn parse_moves(text: &str) -> Result<Vec<Direction>, String> {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.parse::<Direction>().map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Reads a board of 16 numbers, each 0 or a power of two.
+fn read_board(path: &PathBuf) -> Result<[u64; 16], String> {
+    let text = read_to_string(path)?;
+
+    parse_board(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Parses a board of 16 numbers separated by whitespace, see `read_board`.
+fn parse_board(text: &str) -> Result<[u64; 16], String> {
+    let cells = text
+        .split_whitespace()
+        .map(|x| x.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let board: [u64; 16] = cells
+        .try_into()
+        .map_err(|_| "a board has 16 cells".to_string())?;
+    if board
+        .iter()
+        .any(|x| *x == 1 || (*x != 0 && !x.is_power_of_two()))
+    {
+        return Err("a cell must be 0 or a power of two".to_string());
+    }
+
+    Ok(board)
+}
+
+/// Returns an error if a new game cannot start from `board`: `verify` would
+/// reject its proof, see `is_start_board`.
+fn check_start_board(board: &[u64; 16]) -> Result<(), String> {
+    if !is_start_board(board) {
+        return Err(
+            "a game must start from a board of at most two tiles, each a 2 or a 4".to_string(),
+        );
+    }
+
+    Ok(())
+}
+
+/// Loads the prover key from `--params`, or generates and saves both keys
+/// there if there are none yet.
+fn prover_key(
+    options: &Options,
+    prover: &GameProver<E1, E2>,
+) -> Result<GameProverKey<E1, E2, S1, S2>, String> {
+    let key = prover.params_key();
+    let digest = prover.public_params().digest();
+    let pk_path = key.path(&options.params, ParamsKind::ProverKey);
+    let vk_path = key.path(&options.params, ParamsKind::VerifierKey);
+    if pk_path.exists() {
+        return load_prover_key(&pk_path, &key, digest)
+            .map_err(|e| format!("{}: {}", pk_path.display(), e));
+    }
+
+    let (pk, vk) = prover
+        .compressed_keys::<S1, S2>()
+        .map_err(|e| e.to_string())?;
+    save_prover_key(&pk_path, &key, digest, &pk)
+        .map_err(|e| format!("{}: {}", pk_path.display(), e))?;
+    save_verifier_key(&vk_path, &key, digest, &vk)
+        .map_err(|e| format!("{}: {}", vk_path.display(), e))?;
+
+    Ok(pk)
+}
+
+/// Returns the rows of a board, with empty cells as dots.
+fn board_rows(board: &[u64]) -> Vec<String> {
+    board
+        .chunks(4)
+        .map(|row| {
+            row.iter()
+                .map(|x| match x {
+                    0 => format!("{:>6}", "."),
+                    x => format!("{:>6}", x),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn print_board(board: &[u64]) {
+    for row in board_rows(board) {
+        println!("{}", row);
+    }
+}
+
+fn setup(options: &Options) -> Result<(), String> {
+    let prover = options.prover()?;
+    prover_key(options, &prover)?;
+
+    let key = prover.params_key();
+    println!(
+        "digest: {:?}",
+        prover.public_params().digest().to_repr().as_ref()
+    );
+    for kind in [
+        ParamsKind::PublicParams,
+        ParamsKind::ProverKey,
+        ParamsKind::VerifierKey,
+    ] {
+        println!("{}", key.path(&options.params, kind).display());
+    }
+
+    Ok(())
+}
+
+/// Proves that the moves of `--moves` are played from `--board`, and writes
+/// the proof bundle to `--proof`.
+///
+/// With `--checkpoint`, the proof is saved after every step, and resumed from
+/// there if the checkpoint exists.
+fn prove(options: &Options) -> Result<(), String> {
+    let game = options.game()?;
+    check_start_board(&game.board)?;
+    let moves = options.moves()?;
+    let prover = options.prover()?;
+
+    let (mut proof, mut remaining) = match &options.checkpoint {
+        Some(path) if path.exists() => {
+            let (proof, remaining) =
+                load_checkpoint(path, &prover).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+            // The last step may be padded with no-ops.
+            let mut checkpoint_moves = [proof.moves(), &remaining[..]].concat();
+            while checkpoint_moves.len() > moves.len()
+                && checkpoint_moves.last() == Some(&Direction::Noop)
+            {
+                checkpoint_moves.pop();
+            }
+            if proof.start().state() != game.state() || checkpoint_moves != moves {
+                return Err(format!(
+                    "{}: the checkpoint is for another game",
+                    path.display()
+                ));
+            }
+
+            println!("resuming after step {}", proof.num_steps());
+            (proof, remaining)
+        }
+        _ => (prover.start(game).map_err(|e| e.to_string())?, moves),
+    };
+
+    while !remaining.is_empty() {
+        let rest = remaining.split_off(remaining.len().min(prover.moves_per_step()));
+        let mut step = std::mem::replace(&mut remaining, rest);
+        step.resize(prover.moves_per_step(), Direction::Noop);
+        prover
+            .prove_step(&mut proof, &step)
+            .map_err(|e| e.to_string())?;
+
+        if let Some(path) = &options.checkpoint {
+            save_checkpoint(path, &prover, &proof, &remaining)
+                .map_err(|e| format!("{}: {}", path.display(), e))?;
+        }
+    }
+    let proof = prover.finish(proof).map_err(|e| e.to_string())?;
+    write_bundle(options, &prover, &proof)?;
+
+    if let Some(path) = &options.checkpoint {
+        fs::remove_file(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Compresses `proof` and writes its bundle to `--proof`.
+fn write_bundle(
+    options: &Options,
+    prover: &GameProver<E1, E2>,
+    proof: &GameProof<E1, E2>,
+) -> Result<(), String> {
+    let pk = prover_key(options, prover)?;
+    let proof = prover.compress(&pk, proof).map_err(|e| e.to_string())?;
+
+    let digest = prover.public_params().digest();
+    let bundle = ProofBundle::new(prover.params_key(), digest, proof);
+    fs::write(&options.proof, bundle.encode())
+        .map_err(|e| format!("{}: {}", options.proof.display(), e))?;
+    println!(
+        "proved {} steps into {}",
+        bundle.proof.num_steps,
+        options.proof.display()
+    );
+
+    Ok(())
+}
+
+fn verify(options: &Options) -> Result<(), String> {
+    let bytes =
+        fs::read(&options.proof).map_err(|e| format!("{}: {}", options.proof.display(), e))?;
+    let (header, _) = decode_header(&bytes).map_err(|e| e.to_string())?;
+
+    let vk_path = header.key.path(&options.params, ParamsKind::VerifierKey);
+    let verifier = GameVerifier::<E1, E2, S1, S2>::load(&vk_path, header.key.clone())
+        .map_err(|e| format!("{}: {}", vk_path.display(), e))?;
+    let state = verify_game(&bytes, &verifier).map_err(|e| e.to_string())?;
+
+    // The rules come from the bundle, which the verifier key was picked by:
+    // they are printed for the reader to check them.
+    println!(
+        "valid proof of {} steps of {} moves",
+        header.num_steps, header.key.moves_per_step
+    );
+    println!("rules: {:?}", header.key.config);
+    match state {
+        RevealedState::Public(state) => {
+            let board: Vec<_> = state.board.iter().map(|x| to_u64(*x)).collect();
+            print_board(&board);
+            println!("score: {}", to_u64(state.score));
+            println!("moves: {}", to_u64(state.moves));
+            println!("max tile: {}", to_u64(state.max_tile));
+            println!("game over: {}", state.game_over == F1::ONE);
+        }
+        RevealedState::Private {
+            score, max_tile, ..
+        } => {
+            println!("score: {}", to_u64(score));
+            println!("max tile: {}", to_u64(max_tile));
+        }
+        RevealedState::Claim {
+            score_threshold,
+            max_tile_threshold,
+            holds,
+            ..
+        } => {
+            println!(
+                "score >= {} and max tile >= {}: {}",
+                to_u64(score_threshold),
+                to_u64(max_tile_threshold),
+                holds
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn replay(options: &Options) -> Result<(), String> {
+    let mut game = options.game()?;
+    print_board(&game.board);
+
+    for (i, direction) in options.moves()?.into_iter().enumerate() {
+        game.play(direction);
+        println!();
+        println!("move {}: {:?}, score {}", i + 1, direction, game.score);
+        print_board(&game.board);
+        if game.game_over {
+            println!("game over");
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Puts the terminal in raw mode on the alternate screen, until dropped.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw(session: &Session<F1>, background: &BackgroundProver<E1, E2>) -> io::Result<()> {
+    let game = session.game();
+    let mut lines = vec![
+        format!(
+            "score {}   moves {}   max tile {}",
+            game.score, game.moves, game.max_tile
+        ),
+        format!(
+            "proved {} of {} steps",
+            background.proven_steps(),
+            background.num_steps()
+        ),
+        String::new(),
+    ];
+    lines.extend(board_rows(&game.board));
+    lines.push(String::new());
+    if session.is_over() {
+        lines.push("game over, press any key to prove the game".to_string());
+    } else {
+        lines.push("arrows, wasd or hjkl to move, q to stop and prove the game".to_string());
+    }
+
+    let mut stdout = io::stdout();
+    queue!(
+        stdout,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0)
+    )?;
+    for line in lines {
+        // Raw mode does not return the cursor to the start of the line.
+        write!(stdout, "{}\r\n", line)?;
+    }
+
+    stdout.flush()
+}
+
+/// Returns the next key pressed, or `None` if there is none for a moment.
+fn next_key() -> io::Result<Option<KeyEvent>> {
+    if !event::poll(Duration::from_millis(250))? {
+        return Ok(None);
+    }
+
+    match event::read()? {
+        Event::Key(key) if key.kind == KeyEventKind::Press => Ok(Some(key)),
+        _ => Ok(None),
+    }
+}
+
+/// Plays until the game is over or the player stops, while `background`
+/// proves the moves.
+fn play_session(
+    session: &mut Session<F1>,
+    background: &mut BackgroundProver<E1, E2>,
+) -> io::Result<()> {
+    let _terminal = RawTerminal::enter()?;
+
+    loop {
+        // Redrawn without a key too, to show the steps being proved.
+        draw(session, background)?;
+        let Some(key) = next_key()? else {
+            continue;
+        };
+        if session.is_over() {
+            return Ok(());
+        }
+
+        let direction = match key.code {
+            KeyCode::Up | KeyCode::Char('w') | KeyCode::Char('k') => Direction::Up,
+            KeyCode::Down | KeyCode::Char('s') | KeyCode::Char('j') => Direction::Down,
+            KeyCode::Left | KeyCode::Char('a') | KeyCode::Char('h') => Direction::Left,
+            KeyCode::Right | KeyCode::Char('d') | KeyCode::Char('l') => Direction::Right,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            _ => continue,
+        };
+        if session.play(direction) {
+            background.push(direction);
+        }
+    }
+}
+
+fn play(options: &Options) -> Result<(), String> {
+    // Checked before the game, which would be lost otherwise.
+    let path = options
+        .moves
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("moves.txt"));
+    if path.exists() && !options.overwrite {
+        return Err(format!(
+            "{}: the file exists, pass --overwrite to replace it",
+            path.display()
+        ));
+    }
+
+    let nonce = options.nonce.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs())
+    });
+    let board = match &options.board {
+        Some(path) => read_board(path)?,
+        None => start_board(nonce),
+    };
+    check_start_board(&board)?;
+
+    let game = options.game_from(board, nonce, options.player.as_deref());
+
+    println!("loading the parameters...");
+    let prover = Arc::new(options.prover()?);
+    let mut background = BackgroundProver::spawn(prover.clone(), game.clone());
+    let mut session = Session::new(game);
+    play_session(&mut session, &mut background).map_err(|e| e.to_string())?;
+
+    let game = session.game();
+    println!(
+        "score {}, {} moves, max tile {}",
+        game.score, game.moves, game.max_tile
+    );
+    if session.moves().is_empty() {
+        return Ok(());
+    }
+
+    // The header lets the game be proved again with `prove --moves` alone,
+    // see `Options::game`.
+    let header = MovesHeader {
+        board: Some(board),
+        nonce: Some(nonce),
+        player: options.player.clone(),
+    };
+    let log = format!("{}{}", header, session.move_log());
+    fs::write(&path, log).map_err(|e| format!("{}: {}", path.display(), e))?;
+    println!("moves written to {}", path.display());
+
+    println!(
+        "proved {} steps while playing, proving the rest...",
+        background.proven_steps()
+    );
+    let proof = background.finish().map_err(|e| e.to_string())?;
+
+    write_bundle(options, &prover, &proof)
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some(command) = args.first() else {
+        eprint!("{}", USAGE);
+        process::exit(2);
+    };
+
+    let res = Options::parse(&args[1..]).and_then(|options| match command.as_str() {
+        "setup" => setup(&options),
+        "prove" => prove(&options),
+        "verify" => verify(&options),
+        "replay" => replay(&options),
+        "play" => play(&options),
+        "help" | "--help" | "-h" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        _ => Err(format!("unknown command {}\n\n{}", command, USAGE)),
+    });
+
+    if let Err(e) = res {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nova_2048::{circuit::GameConfig, game::Direction};
+
+    use super::{check_start_board, parse_board, parse_header, parse_moves, MovesHeader, Options};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = Options::parse(&[]).unwrap();
+        assert_eq!(options.moves_per_step, 10);
+        assert_eq!(options.config, GameConfig::default());
+        assert_eq!(options.nonce, None);
+
+        let options = Options::parse(&args(&[
+            "--moves-per-step",
+            "3",
+            "--nonce",
+            "42",
+            "--player",
+            "alice",
+            "--target",
+            "512",
+            "--tile-sum",
+            "--board-history",
+        ]))
+        .unwrap();
+        assert_eq!(options.moves_per_step, 3);
+        assert_eq!(options.nonce, Some(42));
+        assert_eq!(options.player.as_deref(), Some("alice"));
+        assert_eq!(options.config.target, 512);
+        assert!(options.config.check_tile_sum);
+        assert!(options.config.board_history);
+
+        assert!(Options::parse(&args(&["--nonce"])).is_err());
+        assert!(Options::parse(&args(&["--nonce", "x"])).is_err());
+        assert!(Options::parse(&args(&["--moves-per-step", "0"])).is_err());
+        assert!(Options::parse(&args(&["--unknown"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_board() {
+        let board = parse_board("0 2 0 0\n0 0 0 0\n4 0 0 0\n0 0 0 2048\n").unwrap();
+        assert_eq!(board[1], 2);
+        assert_eq!(board[8], 4);
+        assert_eq!(board[15], 2048);
+
+        assert!(parse_board("0 2 0 0").is_err());
+        assert!(parse_board(&"0 ".repeat(17)).is_err());
+        assert!(parse_board(&format!("3 {}", "0 ".repeat(15))).is_err());
+        assert!(parse_board(&format!("1 {}", "0 ".repeat(15))).is_err());
+        assert!(parse_board(&format!("x {}", "0 ".repeat(15))).is_err());
+
+        // A valid board, but not the start of a game.
+        assert!(check_start_board(&board).is_err());
+        let mut board = [0; 16];
+        board[0] = 2;
+        board[5] = 4;
+        assert!(check_start_board(&board).is_ok());
+    }
+
+    #[test]
+    fn test_parse_moves() {
+        let moves = parse_moves("# nonce: 1\nup, d\n  # left\nLeft r\n\n").unwrap();
+        assert_eq!(
+            moves,
+            [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right
+            ]
+        );
+
+        assert!(parse_moves("up sideways").is_err());
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let mut board = [0; 16];
+        board[3] = 2;
+        board[12] = 4;
+        let header = MovesHeader {
+            board: Some(board),
+            nonce: Some(7),
+            player: Some("alice".to_string()),
+        };
+        let text = format!("{}# a comment: with a colon\nup\nleft\n", header);
+        assert_eq!(parse_header(&text), Ok(header));
+        assert_eq!(parse_moves(&text), Ok(vec![Direction::Up, Direction::Left]));
+
+        assert_eq!(parse_header("up\n# left\n"), Ok(MovesHeader::default()));
+        assert!(parse_header("# nonce: x\n").is_err());
+        assert!(parse_header("# board: 2 2\n").is_err());
+    }
+}
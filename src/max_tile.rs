@@ -0,0 +1,219 @@
+use bellpepper_core::{boolean::AllocatedBit, num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use crate::NumConstraintSystem;
+
+/// The number of bits of a tile. A 4x4 board cannot hold a tile above 2^17,
+/// so 32 bits leave plenty of room.
+pub const NUM_TILE_BITS: usize = 32;
+
+/// Returns a bit that equals 1 if and only if `a` >= `b`.
+///
+/// Both `a` and `b` must be less than 2^NUM_TILE_BITS.
+pub fn greater_or_equal<F: PrimeField, CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    a: &AllocatedNum<F>,
+    b: &AllocatedNum<F>,
+) -> Result<AllocatedBit, SynthesisError> {
+    // a - b + 2^NUM_TILE_BITS has its top bit set if and only if a >= b.
+    let shift = F::from(1 << NUM_TILE_BITS);
+    let diff = AllocatedNum::alloc(cs.namespace(|| "alloc_diff"), || {
+        Ok(a.get_value().unwrap_or(F::ZERO) - b.get_value().unwrap_or(F::ZERO) + shift)
+    })?;
+
+    cs.enforce(
+        || "enforce_(a - b + shift = diff)",
+        |lc| lc + a.get_variable() - b.get_variable() + (shift, CS::one()),
+        |lc| lc + CS::one(),
+        |lc| lc + diff.get_variable(),
+    );
+
+    let mut bits = diff.to_bits(cs.namespace(|| "diff_bits"), NUM_TILE_BITS + 1)?;
+
+    Ok(bits.pop().unwrap())
+}
+
+/// Computes max{max_tile, max{board}}, the maximum tile ever seen.
+pub struct MaxTile<F: PrimeField> {
+    pub max_tile: AllocatedNum<F>,
+
+    pub board: Vec<AllocatedNum<F>>,
+}
+
+impl<F: PrimeField> MaxTile<F> {
+    pub fn new(max_tile: &AllocatedNum<F>, board: &[AllocatedNum<F>]) -> Self {
+        assert_eq!(board.len(), 16);
+
+        Self {
+            max_tile: max_tile.clone(),
+            board: board.to_vec(),
+        }
+    }
+
+    pub fn synthesize<CS: ConstraintSystem<F>>(
+        &self,
+        mut cs: CS,
+    ) -> Result<AllocatedNum<F>, SynthesisError> {
+        let mut max = self.max_tile.clone();
+        for (i, x) in self.board.iter().enumerate() {
+            let bit = greater_or_equal(cs.namespace(|| format!("compare_{}", i)), &max, x)?;
+
+            let value = if bit.get_value().unwrap_or(false) {
+                max.get_value()
+            } else {
+                x.get_value()
+            };
+            let new_max = AllocatedNum::alloc(cs.namespace(|| format!("alloc_max_{}", i)), || {
+                Ok(value.unwrap_or(F::ZERO))
+            })?;
+
+            cs.enforce(
+                || format!("enforce_(bit * (max - x) = new_max - x)_{}", i),
+                |lc| lc + bit.get_variable(),
+                |lc| lc + max.get_variable() - x.get_variable(),
+                |lc| lc + new_max.get_variable() - x.get_variable(),
+            );
+
+            max = new_max;
+        }
+
+        Ok(max)
+    }
+}
+
+/// Latches `reached` to 1 the first time `max_tile` >= `target`, and records
+/// the number of moves at that time in `reached_at`.
+pub struct ReachedTarget<F: PrimeField> {
+    pub max_tile: AllocatedNum<F>,
+
+    pub target: u64,
+
+    pub reached: AllocatedNum<F>,
+
+    pub reached_at: AllocatedNum<F>,
+
+    pub moves: AllocatedNum<F>,
+}
+
+impl<F: PrimeField> ReachedTarget<F> {
+    pub fn new(
+        max_tile: &AllocatedNum<F>,
+        target: u64,
+        reached: &AllocatedNum<F>,
+        reached_at: &AllocatedNum<F>,
+        moves: &AllocatedNum<F>,
+    ) -> Self {
+        assert!(target < 1 << NUM_TILE_BITS);
+
+        Self {
+            max_tile: max_tile.clone(),
+            target,
+            reached: reached.clone(),
+            reached_at: reached_at.clone(),
+            moves: moves.clone(),
+        }
+    }
+
+    /// Returns the new `reached` and `reached_at`.
+    pub fn synthesize<CS: ConstraintSystem<F>>(
+        &self,
+        mut cs: CS,
+    ) -> Result<(AllocatedNum<F>, AllocatedNum<F>), SynthesisError> {
+        let target = F::from(self.target);
+        let target_var = AllocatedNum::alloc(cs.namespace(|| "alloc_target"), || Ok(target))?;
+        cs.enforce(
+            || "enforce_target",
+            |lc| lc + target_var.get_variable(),
+            |lc| lc + CS::one(),
+            |lc| lc + (target, CS::one()),
+        );
+
+        let bit = greater_or_equal(cs.namespace(|| "compare"), &self.max_tile, &target_var)?;
+
+        // reached | bit = reached + bit - reached * bit
+        let old = self.reached.get_value().unwrap_or(F::ZERO);
+        let value = if bit.get_value().unwrap_or(false) {
+            F::ONE
+        } else {
+            old
+        };
+        let reached = AllocatedNum::alloc(cs.namespace(|| "alloc_reached"), || Ok(value))?;
+        cs.enforce(
+            || "enforce_(old * (1 - bit) = reached - bit)",
+            |lc| lc + self.reached.get_variable(),
+            |lc| lc + CS::one() - bit.get_variable(),
+            |lc| lc + reached.get_variable() - bit.get_variable(),
+        );
+
+        // reached_at only changes in the move which sets the latch.
+        let first = value - old;
+        let old_at = self.reached_at.get_value().unwrap_or(F::ZERO);
+        let moves = self.moves.get_value().unwrap_or(F::ZERO);
+        let reached_at = AllocatedNum::alloc(cs.namespace(|| "alloc_reached_at"), || {
+            Ok(old_at + first * (moves - old_at))
+        })?;
+        cs.enforce(
+            || "enforce_((reached - old) * (moves - old_at) = reached_at - old_at)",
+            |lc| lc + reached.get_variable() - self.reached.get_variable(),
+            |lc| lc + self.moves.get_variable() - self.reached_at.get_variable(),
+            |lc| lc + reached_at.get_variable() - self.reached_at.get_variable(),
+        );
+
+        Ok((reached, reached_at))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+    use blstrs::Scalar as Fr;
+
+    use super::{greater_or_equal, MaxTile};
+
+    #[test]
+    fn test_greater_or_equal() {
+        for (a, b, expected) in [
+            (0, 0, true),
+            (2, 4, false),
+            (4, 2, true),
+            (1 << 17, 2048, true),
+        ] {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let a = AllocatedNum::alloc(cs.namespace(|| "a"), || Ok(Fr::from(a))).unwrap();
+            let b = AllocatedNum::alloc(cs.namespace(|| "b"), || Ok(Fr::from(b))).unwrap();
+
+            let bit = greater_or_equal(cs.namespace(|| "compare"), &a, &b).unwrap();
+            assert_eq!(bit.get_value(), Some(expected));
+            assert!(cs.is_satisfied());
+        }
+    }
+
+    #[test]
+    fn test_max_tile() {
+        #[rustfmt::skip]
+        let board = [
+            2,  0,   4, 0,
+            0,  128, 0, 0,
+            16, 0,   0, 8,
+            0,  0,   2, 0,
+        ];
+
+        for (old_max, expected) in [(0, 128), (64, 128), (256, 256)] {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let old_max =
+                AllocatedNum::alloc(cs.namespace(|| "max_tile"), || Ok(Fr::from(old_max))).unwrap();
+            let mut board_vars = Vec::new();
+            for (i, x) in board.iter().enumerate() {
+                board_vars.push(
+                    AllocatedNum::alloc(cs.namespace(|| format!("board_{i}")), || Ok(Fr::from(*x)))
+                        .unwrap(),
+                );
+            }
+
+            let circuit = MaxTile::new(&old_max, &board_vars);
+            let max = circuit.synthesize(cs.namespace(|| "max")).unwrap();
+            assert_eq!(max.get_value(), Some(Fr::from(expected)));
+            assert!(cs.is_satisfied());
+        }
+    }
+}
@@ -7,6 +7,10 @@ pub struct Merge<F: PrimeField> {
     pub sorted_lines: Vec<Vec<AllocatedNum<F>>>,
 
     pub merged_lines: Vec<Vec<AllocatedNum<F>>>,
+
+    /// The number created by each merge, 0 if the two numbers are not merged.
+    /// Their sum is what the move adds to the score.
+    pub merged_numbers: Vec<AllocatedNum<F>>,
 }
 
 impl<F: PrimeField> Merge<F> {
@@ -17,6 +21,7 @@ impl<F: PrimeField> Merge<F> {
         Self {
             sorted_lines: sorted_lines.to_vec(),
             merged_lines: vec![],
+            merged_numbers: vec![],
         }
     }
 
@@ -25,6 +30,7 @@ impl<F: PrimeField> Merge<F> {
         mut cs: CS,
     ) -> Result<(), SynthesisError> {
         let mut namespace_index = 0;
+        let mut merged_numbers = Vec::new();
 
         // Takes two allocated numbers (a, b) and returns
         // (2 * a, 0) if and only if `a` == `b` , and (a, b)
@@ -68,6 +74,20 @@ impl<F: PrimeField> Merge<F> {
                 |lc| lc + b.get_variable() - d_var.get_variable(),
             );
 
+            // c - a = a * bit, so the merged number 2 * a * bit is 2 * (c - a).
+            let merged = (c - a.get_value().unwrap_or(F::ZERO)).double();
+            let merged_var = AllocatedNum::alloc(
+                cs.namespace(|| format!("alloc_merged_{}", namespace_index)),
+                || Ok(merged),
+            )?;
+            cs.enforce(
+                || format!("enforce_(merged = 2 * (c - a))_{}", namespace_index),
+                |lc| lc + c_var.get_variable() - a.get_variable(),
+                |lc| lc + CS::one() + CS::one(),
+                |lc| lc + merged_var.get_variable(),
+            );
+            merged_numbers.push(merged_var);
+
             namespace_index += 1;
 
             Ok((c_var, d_var))
@@ -84,6 +104,7 @@ impl<F: PrimeField> Merge<F> {
         }
 
         self.merged_lines = merged_lines;
+        self.merged_numbers = merged_numbers;
 
         Ok(())
     }
@@ -283,6 +304,22 @@ mod test {
         assert_eq!(merged_lines[1], vec![four, zero, two, zero]);
         assert_eq!(merged_lines[2], vec![two, eight, two, zero]);
         assert_eq!(merged_lines[3], vec![four, zero, zero, zero]);
+
+        let merged_numbers: Vec<_> = step_2
+            .merged_numbers
+            .iter()
+            .map(|x| x.get_value().unwrap())
+            .collect();
+        #[rustfmt::skip]
+        assert_eq!(
+            merged_numbers,
+            vec![
+                zero, four, zero,
+                four, zero, zero,
+                zero, zero, zero,
+                four, zero, zero,
+            ]
+        );
     }
 
     #[test]
@@ -0,0 +1,167 @@
+use bellpepper_core::{
+    boolean::AllocatedBit, num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+
+/// The number of tile levels a merge can create: 4 = 2^2 up to 2^17, the
+/// largest tile of a 4x4 board.
+pub const NUM_LEVELS: usize = 16;
+
+/// The exponent of the tile counted at level 0.
+pub const MIN_LEVEL_EXPONENT: usize = 2;
+
+/// Returns the level of a tile created by a merge, see `NUM_LEVELS`.
+pub fn level(tile: u64) -> usize {
+    assert!(tile.is_power_of_two());
+
+    tile.trailing_zeros() as usize - MIN_LEVEL_EXPONENT
+}
+
+/// Counts the merges of a move by the level of the tile they create.
+///
+/// Every merged number of `Merge` is decomposed into one bit per level, with
+/// at most one bit set, so counters[k] += number of merged numbers = 2^(k + 2).
+pub struct MergeCounter<F: PrimeField> {
+    pub counters: Vec<AllocatedNum<F>>,
+
+    pub merged_numbers: Vec<AllocatedNum<F>>,
+}
+
+impl<F: PrimeField> MergeCounter<F> {
+    pub fn new(counters: &[AllocatedNum<F>], merged_numbers: &[AllocatedNum<F>]) -> Self {
+        assert_eq!(counters.len(), NUM_LEVELS);
+
+        Self {
+            counters: counters.to_vec(),
+            merged_numbers: merged_numbers.to_vec(),
+        }
+    }
+
+    /// Returns the new counters.
+    pub fn synthesize<CS: ConstraintSystem<F>>(
+        &self,
+        mut cs: CS,
+    ) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+        let mut level_bits = Vec::new();
+        for (i, x) in self.merged_numbers.iter().enumerate() {
+            let value = x.get_value().unwrap_or(F::ZERO);
+
+            let mut bits = Vec::new();
+            for k in 0..NUM_LEVELS {
+                let tile = F::from(1 << (k + MIN_LEVEL_EXPONENT));
+                bits.push(AllocatedBit::alloc(
+                    cs.namespace(|| format!("alloc_bit_{}_{}", i, k)),
+                    Some(value == tile),
+                )?);
+            }
+
+            let mut sum_lc = LinearCombination::zero();
+            let mut tile_lc = LinearCombination::zero();
+            for (k, bit) in bits.iter().enumerate() {
+                sum_lc = sum_lc + bit.get_variable();
+                tile_lc = tile_lc + (F::from(1 << (k + MIN_LEVEL_EXPONENT)), bit.get_variable());
+            }
+
+            cs.enforce(
+                || format!("enforce_(sum of bits is boolean)_{}", i),
+                |lc| lc + &sum_lc,
+                |lc| lc + CS::one() - &sum_lc,
+                |lc| lc,
+            );
+
+            cs.enforce(
+                || format!("enforce_(sum of bits * tile = merged_number)_{}", i),
+                |lc| lc + &tile_lc,
+                |lc| lc + CS::one(),
+                |lc| lc + x.get_variable(),
+            );
+
+            level_bits.push(bits);
+        }
+
+        let mut counters = Vec::new();
+        for (k, counter) in self.counters.iter().enumerate() {
+            let mut value = counter.get_value().unwrap_or(F::ZERO);
+            for bits in level_bits.iter() {
+                if bits[k].get_value().unwrap_or(false) {
+                    value += F::ONE;
+                }
+            }
+            let new_counter =
+                AllocatedNum::alloc(cs.namespace(|| format!("alloc_counter_{}", k)), || {
+                    Ok(value)
+                })?;
+
+            cs.enforce(
+                || format!("enforce_counter_{}", k),
+                |lc| {
+                    let mut sum = lc + counter.get_variable();
+                    for bits in level_bits.iter() {
+                        sum = sum + bits[k].get_variable();
+                    }
+
+                    sum
+                },
+                |lc| lc + CS::one(),
+                |lc| lc + new_counter.get_variable(),
+            );
+
+            counters.push(new_counter);
+        }
+
+        Ok(counters)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+    use blstrs::Scalar as Fr;
+
+    use super::{MergeCounter, NUM_LEVELS};
+
+    fn synthesize(counters: &[u64], merged_numbers: &[u64]) -> (bool, Vec<Fr>) {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+
+        let mut counter_vars = Vec::new();
+        for (i, x) in counters.iter().enumerate() {
+            counter_vars.push(
+                AllocatedNum::alloc(cs.namespace(|| format!("counter_{i}")), || Ok(Fr::from(*x)))
+                    .unwrap(),
+            );
+        }
+        let mut merged_vars = Vec::new();
+        for (i, x) in merged_numbers.iter().enumerate() {
+            merged_vars.push(
+                AllocatedNum::alloc(cs.namespace(|| format!("merged_{i}")), || Ok(Fr::from(*x)))
+                    .unwrap(),
+            );
+        }
+
+        let circuit = MergeCounter::new(&counter_vars, &merged_vars);
+        let counters = circuit.synthesize(cs.namespace(|| "count")).unwrap();
+
+        let counters = counters.iter().map(|x| x.get_value().unwrap()).collect();
+        (cs.is_satisfied(), counters)
+    }
+
+    #[test]
+    fn test_merge_counter() {
+        let mut counters = vec![0; NUM_LEVELS];
+        counters[1] = 5;
+
+        let (satisfied, new_counters) = synthesize(&counters, &[4, 0, 8, 4, 0, 1 << 17]);
+        assert!(satisfied);
+
+        let mut expected = counters.clone();
+        expected[0] += 2;
+        expected[1] += 1;
+        expected[15] += 1;
+        let expected: Vec<_> = expected.into_iter().map(Fr::from).collect();
+        assert_eq!(new_counters, expected);
+
+        // Not a tile a merge can create.
+        let (satisfied, _) = synthesize(&counters, &[4, 12]);
+        assert!(!satisfied);
+    }
+}
@@ -0,0 +1,274 @@
+use bellpepper_core::{
+    boolean::AllocatedBit, num::AllocatedNum, ConstraintSystem, LinearCombination, SynthesisError,
+};
+use ff::PrimeField;
+
+use crate::NumConstraintSystem;
+
+/// The number of bits of the exponent of a cell. A cell holds 0 or 2^e with
+/// 1 <= e < 2^NUM_EXPONENT_BITS, so 5 bits cover every reachable tile.
+pub const NUM_EXPONENT_BITS: usize = 5;
+
+/// The number of bits of a packed board.
+pub const NUM_PACKED_BITS: usize = 16 * NUM_EXPONENT_BITS;
+
+/// Returns the exponent of a cell, 0 for an empty cell, or `None` if the cell
+/// is not 0 or 2^e with 1 <= e < 2^NUM_EXPONENT_BITS.
+fn exponent<F: PrimeField>(x: F) -> Option<u64> {
+    let repr = x.to_repr();
+    let value = u64::from_le_bytes(repr.as_ref()[..8].try_into().unwrap());
+    if F::from(value) != x {
+        return None;
+    }
+
+    match value {
+        0 => Some(0),
+        1 => None,
+        _ if value.is_power_of_two() && value.trailing_zeros() < 1 << NUM_EXPONENT_BITS => {
+            Some(value.trailing_zeros() as u64)
+        }
+        _ => None,
+    }
+}
+
+/// Packs the 16 cells of a board into one element, cell i taking bits
+/// [5 * i, 5 * i + 5) of it with its exponent.
+pub fn pack<F: PrimeField>(board: &[F]) -> F {
+    assert_eq!(board.len(), 16);
+
+    let mut packed = F::ZERO;
+    for x in board.iter().rev() {
+        let e = exponent(*x).expect("a cell must be 0 or a power of two");
+        packed = packed * F::from(1 << NUM_EXPONENT_BITS) + F::from(e);
+    }
+
+    packed
+}
+
+/// Unpacks a board packed by `pack`, or returns `None` if `packed` is not
+/// below 2^NUM_PACKED_BITS.
+pub fn unpack<F: PrimeField>(packed: F) -> Option<Vec<F>> {
+    let repr = packed.to_repr();
+    let bytes = repr.as_ref();
+    if bytes[NUM_PACKED_BITS / 8..].iter().any(|b| *b != 0) {
+        return None;
+    }
+
+    let mut low = [0; 16];
+    low[..NUM_PACKED_BITS / 8].copy_from_slice(&bytes[..NUM_PACKED_BITS / 8]);
+    let mut value = u128::from_le_bytes(low);
+
+    let mut board = Vec::new();
+    for _ in 0..16 {
+        let e = (value % (1 << NUM_EXPONENT_BITS)) as u32;
+        board.push(if e == 0 { F::ZERO } else { F::from(1 << e) });
+        value >>= NUM_EXPONENT_BITS;
+    }
+
+    Some(board)
+}
+
+/// Returns 2^e from the bits of e, or 0 if e = 0.
+fn cell_from_exponent<F: PrimeField, CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    bits: &[AllocatedBit],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+    assert_eq!(bits.len(), NUM_EXPONENT_BITS);
+
+    let bit_value = |bit: &AllocatedBit| -> F {
+        if bit.get_value().unwrap_or(false) {
+            F::ONE
+        } else {
+            F::ZERO
+        }
+    };
+
+    // 2^e = prod{1 + bit_i * (2^(2^i) - 1)}, and power_0 = 1 + bit_0 is linear.
+    let factor = |i: usize| F::from(1 << (1 << i)) - F::ONE;
+    let mut power_value = F::ONE + bit_value(&bits[0]) * factor(0);
+    let mut power_lc = LinearCombination::zero() + CS::one() + (factor(0), bits[0].get_variable());
+    for (i, bit) in bits.iter().enumerate().skip(1) {
+        power_value *= F::ONE + bit_value(bit) * factor(i);
+        let power = AllocatedNum::alloc(cs.namespace(|| format!("alloc_power_{}", i)), || {
+            Ok(power_value)
+        })?;
+
+        cs.enforce(
+            || format!("enforce_power_{}", i),
+            |lc| lc + &power_lc,
+            |lc| lc + CS::one() + (factor(i), bit.get_variable()),
+            |lc| lc + power.get_variable(),
+        );
+
+        power_lc = LinearCombination::zero() + power.get_variable();
+    }
+
+    // The cell is empty if and only if e = 0, and then 2^e = 1.
+    let mut exponent_value = F::ZERO;
+    let mut exponent_lc = LinearCombination::zero();
+    let mut coeff = F::ONE;
+    for bit in bits.iter() {
+        exponent_value += bit_value(bit) * coeff;
+        exponent_lc = exponent_lc + (coeff, bit.get_variable());
+        coeff = coeff.double();
+    }
+    let exponent = AllocatedNum::alloc(cs.namespace(|| "alloc_exponent"), || Ok(exponent_value))?;
+    cs.enforce(
+        || "enforce_exponent",
+        |lc| lc + &exponent_lc,
+        |lc| lc + CS::one(),
+        |lc| lc + exponent.get_variable(),
+    );
+    let is_empty = exponent.is_equal_to_zero_bit(cs.namespace(|| "is_empty"))?;
+
+    let cell_value = if is_empty.get_value().unwrap_or(false) {
+        F::ZERO
+    } else {
+        power_value
+    };
+    let cell = AllocatedNum::alloc(cs.namespace(|| "alloc_cell"), || Ok(cell_value))?;
+    cs.enforce(
+        || "enforce_(cell = power - is_empty)",
+        |lc| lc + &power_lc - is_empty.get_variable(),
+        |lc| lc + CS::one(),
+        |lc| lc + cell.get_variable(),
+    );
+
+    Ok(cell)
+}
+
+/// Unpacks a board packed by `pack`, enforcing that `packed` < 2^NUM_PACKED_BITS.
+pub fn unpack_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    packed: &AllocatedNum<F>,
+) -> Result<Vec<AllocatedNum<F>>, SynthesisError> {
+    let bits = packed.to_bits(cs.namespace(|| "bits"), NUM_PACKED_BITS)?;
+
+    let mut board = Vec::new();
+    for (i, chunk) in bits.chunks(NUM_EXPONENT_BITS).enumerate() {
+        board.push(cell_from_exponent(
+            cs.namespace(|| format!("cell_{}", i)),
+            chunk,
+        )?);
+    }
+
+    Ok(board)
+}
+
+/// Packs a board as `pack` does, enforcing that every cell is 0 or a power of
+/// two with an exponent below 2^NUM_EXPONENT_BITS.
+pub fn pack_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    board: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+    assert_eq!(board.len(), 16);
+
+    let mut bits = Vec::new();
+    for (i, x) in board.iter().enumerate() {
+        // A cell which is not a power of two gets a wrong exponent, and then
+        // fails the equality below.
+        let e = exponent(x.get_value().unwrap_or(F::ZERO)).unwrap_or(0);
+
+        let mut cell_bits = Vec::new();
+        for j in 0..NUM_EXPONENT_BITS {
+            cell_bits.push(AllocatedBit::alloc(
+                cs.namespace(|| format!("alloc_bit_{}_{}", i, j)),
+                Some((e >> j) & 1 == 1),
+            )?);
+        }
+
+        let cell = cell_from_exponent(cs.namespace(|| format!("cell_{}", i)), &cell_bits)?;
+        cs.enforce(
+            || format!("enforce_cell_{}", i),
+            |lc| lc + cell.get_variable(),
+            |lc| lc + CS::one(),
+            |lc| lc + x.get_variable(),
+        );
+
+        bits.extend(cell_bits);
+    }
+
+    let mut value = F::ZERO;
+    let mut coeff = F::ONE;
+    let mut packed_lc = LinearCombination::zero();
+    for bit in bits.iter() {
+        if bit.get_value().unwrap_or(false) {
+            value += coeff;
+        }
+        packed_lc = packed_lc + (coeff, bit.get_variable());
+        coeff = coeff.double();
+    }
+    let packed = AllocatedNum::alloc(cs.namespace(|| "alloc_packed"), || Ok(value))?;
+
+    cs.enforce(
+        || "enforce_(sum of bits = packed)",
+        |lc| lc + &packed_lc,
+        |lc| lc + CS::one(),
+        |lc| lc + packed.get_variable(),
+    );
+
+    Ok(packed)
+}
+
+#[cfg(test)]
+mod test {
+    use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+    use blstrs::Scalar as Fr;
+    use ff::PrimeField;
+
+    use super::{pack, pack_circuit, unpack, unpack_circuit};
+
+    #[test]
+    fn test_pack() {
+        #[rustfmt::skip]
+        let board: Vec<_> = [
+            0,    2,    4, 0,
+            2048, 0,    0, 8,
+            0,    1024, 0, 0,
+            0,    0,    0, 1 << 17,
+        ]
+        .iter()
+        .map(|x| Fr::from(*x))
+        .collect();
+
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let mut board_vars = Vec::new();
+        for (i, x) in board.iter().enumerate() {
+            board_vars.push(
+                AllocatedNum::alloc(cs.namespace(|| format!("board_{i}")), || Ok(*x)).unwrap(),
+            );
+        }
+
+        let packed = pack_circuit(cs.namespace(|| "pack"), &board_vars).unwrap();
+        assert_eq!(packed.get_value(), Some(pack(&board)));
+        assert_eq!(
+            pack(&board),
+            Fr::from_u128((1 << 5) + (2 << 10) + (11 << 20) + (3 << 35) + (10 << 45) + (17 << 75))
+        );
+
+        let unpacked = unpack_circuit(cs.namespace(|| "unpack"), &packed).unwrap();
+        let unpacked: Vec<_> = unpacked.iter().map(|x| x.get_value().unwrap()).collect();
+        assert_eq!(unpacked, board);
+        assert_eq!(unpack(pack(&board)), Some(board));
+        assert_eq!(unpack(Fr::from_u128(1 << 80)), None);
+
+        assert!(cs.is_satisfied());
+    }
+
+    #[test]
+    fn test_pack_non_power() {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+        let mut board_vars = Vec::new();
+        for i in 0..16 {
+            board_vars.push(
+                AllocatedNum::alloc(cs.namespace(|| format!("board_{i}")), || {
+                    Ok(Fr::from(if i == 3 { 6 } else { 0 }))
+                })
+                .unwrap(),
+            );
+        }
+
+        pack_circuit(cs.namespace(|| "pack"), &board_vars).unwrap();
+        assert!(!cs.is_satisfied());
+    }
+}
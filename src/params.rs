@@ -0,0 +1,569 @@
+//! Public parameters and compressed-SNARK keys on disk.
+//!
+//! A file holds a `ParamsHeader` and the bincode encoding of the value. The
+//! header names what the value was generated for (a digest of the circuit,
+//! the `GameConfig` and the moves per step), the digest of the public
+//! parameters and a checksum of the value, checked when the file is loaded.
+//!
+//! The digest of the circuit is computed from the R1CS shape of the step
+//! circuit, see `circuit_digest`, so a change to the circuit gives new files
+//! instead of loading parameters which no longer fit it.
+
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use bellpepper_core::{
+    num::AllocatedNum, ConstraintSystem, Index, LinearCombination, SynthesisError, Variable,
+};
+use ff::PrimeField;
+use nova_snark::traits::{circuit::StepCircuit, snark::RelaxedR1CSSNARKTrait, Group};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+use crate::{
+    circuit::{Game2048Circuit, GameConfig},
+    prover::{GameProverKey, GamePublicParams, GameVerifierKey},
+};
+
+/// The version of the file format, bumped on every incompatible change.
+pub const PARAMS_FORMAT_VERSION: u32 = 1;
+
+/// What a file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParamsKind {
+    PublicParams,
+    ProverKey,
+    VerifierKey,
+}
+
+impl ParamsKind {
+    fn name(self) -> &'static str {
+        match self {
+            ParamsKind::PublicParams => "pp",
+            ParamsKind::ProverKey => "pk",
+            ParamsKind::VerifierKey => "vk",
+        }
+    }
+}
+
+/// What public parameters are generated for. The same key always gives the
+/// same circuit, and so the same digest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamsKey {
+    /// The digest of the step circuit, see `circuit_digest`.
+    pub circuit: Vec<u8>,
+
+    pub config: GameConfig,
+
+    pub moves_per_step: usize,
+}
+
+impl ParamsKey {
+    /// Returns the key of the circuit of `config` and `moves_per_step` on the
+    /// primary curve `G1`, which synthesizes the circuit.
+    pub fn new<G1: Group>(config: GameConfig, moves_per_step: usize) -> Self {
+        Self {
+            circuit: circuit_digest::<G1>(config, moves_per_step),
+            config,
+            moves_per_step,
+        }
+    }
+
+    /// Returns the name of the file of `kind` for this key, which tells the
+    /// keys apart by the digest of the circuit.
+    pub fn file_name(&self, kind: ParamsKind) -> String {
+        let hex: String = self.circuit[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        format!("{}-{}-{}.bin", kind.name(), hex, self.moves_per_step)
+    }
+
+    /// Returns the path of the file of `kind` for this key in `dir`.
+    pub fn path(&self, dir: &Path, kind: ParamsKind) -> PathBuf {
+        dir.join(self.file_name(kind))
+    }
+}
+
+/// Returns the Keccak-256 hash of the R1CS shape of the step circuit of
+/// `config` and `moves_per_step` on the primary curve `G1`: the moduli of the
+/// curve cycle, the numbers of variables and every constraint.
+pub fn circuit_digest<G1: Group>(config: GameConfig, moves_per_step: usize) -> Vec<u8> {
+    let circuit = Game2048Circuit::<G1::Scalar>::blank(moves_per_step, config);
+    let mut cs = ShapeHasher::<G1::Scalar>::default();
+    let z = (0..circuit.arity())
+        .map(|i| {
+            AllocatedNum::alloc(cs.namespace(|| format!("z{}", i)), || {
+                Err(SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    circuit.synthesize(&mut cs, &z).unwrap();
+
+    let mut hasher = Keccak256::new();
+    hasher.update(G1::Scalar::MODULUS.as_bytes());
+    hasher.update(G1::Base::MODULUS.as_bytes());
+    hasher.update((cs.inputs as u64).to_be_bytes());
+    hasher.update((cs.aux as u64).to_be_bytes());
+    hasher.update((cs.constraints as u64).to_be_bytes());
+    hasher.update(cs.hasher.finalize());
+    hasher.finalize().to_vec()
+}
+
+/// A constraint system which only hashes its constraints, as they are
+/// enforced, without computing the assignments.
+struct ShapeHasher<F: PrimeField> {
+    inputs: usize,
+    aux: usize,
+    constraints: usize,
+    hasher: Keccak256,
+    _field: std::marker::PhantomData<F>,
+}
+
+impl<F: PrimeField> Default for ShapeHasher<F> {
+    fn default() -> Self {
+        Self {
+            // The input of the constant one.
+            inputs: 1,
+            aux: 0,
+            constraints: 0,
+            hasher: Keccak256::new(),
+            _field: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> ShapeHasher<F> {
+    fn hash_lc(&mut self, lc: &LinearCombination<F>) {
+        let terms: Vec<_> = lc.iter().collect();
+        self.hasher.update((terms.len() as u64).to_be_bytes());
+        for (var, coeff) in terms {
+            let (kind, index) = match var.get_unchecked() {
+                Index::Input(i) => (b'I', i),
+                Index::Aux(i) => (b'A', i),
+            };
+            self.hasher.update([kind]);
+            self.hasher.update((index as u64).to_be_bytes());
+            self.hasher.update(coeff.to_repr());
+        }
+    }
+}
+
+impl<F: PrimeField> ConstraintSystem<F> for ShapeHasher<F> {
+    type Root = Self;
+
+    fn alloc<FN, A, AR>(&mut self, _annotation: A, _f: FN) -> Result<Variable, SynthesisError>
+    where
+        FN: FnOnce() -> Result<F, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.aux += 1;
+        Ok(Variable::new_unchecked(Index::Aux(self.aux - 1)))
+    }
+
+    fn alloc_input<FN, A, AR>(&mut self, _annotation: A, _f: FN) -> Result<Variable, SynthesisError>
+    where
+        FN: FnOnce() -> Result<F, SynthesisError>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.inputs += 1;
+        Ok(Variable::new_unchecked(Index::Input(self.inputs - 1)))
+    }
+
+    fn enforce<A, AR, LA, LB, LC>(&mut self, _annotation: A, a: LA, b: LB, c: LC)
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+        LA: FnOnce(LinearCombination<F>) -> LinearCombination<F>,
+        LB: FnOnce(LinearCombination<F>) -> LinearCombination<F>,
+        LC: FnOnce(LinearCombination<F>) -> LinearCombination<F>,
+    {
+        self.constraints += 1;
+        self.hash_lc(&a(LinearCombination::zero()));
+        self.hash_lc(&b(LinearCombination::zero()));
+        self.hash_lc(&c(LinearCombination::zero()));
+    }
+
+    fn push_namespace<NR, N>(&mut self, _name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self) {}
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+}
+
+/// The header of a file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamsHeader {
+    pub version: u32,
+
+    pub kind: ParamsKind,
+
+    pub key: ParamsKey,
+
+    /// The digest of the public parameters, which the keys were generated
+    /// from for a key file.
+    pub digest: Vec<u8>,
+
+    /// The Keccak-256 hash of the encoded value.
+    pub checksum: Vec<u8>,
+}
+
+/// The reason a file cannot be saved or loaded.
+#[derive(Debug)]
+pub enum ParamsError {
+    Io(io::Error),
+
+    /// The file is not a valid encoding.
+    Malformed(bincode::Error),
+
+    /// The file has another format version.
+    WrongVersion(u32),
+
+    /// The file holds another kind of value.
+    WrongKind(ParamsKind),
+
+    /// The file was generated for another circuit, config or number of moves
+    /// per step.
+    WrongKey(ParamsKey),
+
+    /// The value does not match the checksum of the header: the file is
+    /// corrupted.
+    WrongChecksum,
+
+    /// The key was generated from other public parameters than the expected
+    /// ones.
+    WrongDigest,
+}
+
+impl fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamsError::Io(e) => write!(f, "cannot access the file: {}", e),
+            ParamsError::Malformed(e) => write!(f, "the file is malformed: {}", e),
+            ParamsError::WrongVersion(version) => write!(
+                f,
+                "the file has format version {}, not {}",
+                version, PARAMS_FORMAT_VERSION
+            ),
+            ParamsError::WrongKind(kind) => write!(f, "the file holds {:?}", kind),
+            ParamsError::WrongKey(key) => write!(f, "the file was generated for {:?}", key),
+            ParamsError::WrongChecksum => write!(f, "the file is corrupted"),
+            ParamsError::WrongDigest => {
+                write!(f, "the digest of the public parameters does not match")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParamsError {}
+
+impl From<io::Error> for ParamsError {
+    fn from(e: io::Error) -> Self {
+        ParamsError::Io(e)
+    }
+}
+
+impl From<bincode::Error> for ParamsError {
+    fn from(e: bincode::Error) -> Self {
+        ParamsError::Malformed(e)
+    }
+}
+
+/// Returns the canonical representation of a field element, as headers hold
+/// digests.
+pub fn element_bytes<F: PrimeField>(digest: F) -> Vec<u8> {
+    digest.to_repr().as_ref().to_vec()
+}
+
+/// Returns the field element of a canonical representation, see
+/// `element_bytes`.
+pub fn element_from_bytes<F: PrimeField>(bytes: &[u8]) -> Option<F> {
+    let mut repr = F::Repr::default();
+    if repr.as_ref().len() != bytes.len() {
+        return None;
+    }
+    repr.as_mut().copy_from_slice(bytes);
+
+    F::from_repr(repr).into()
+}
+
+fn save<T: Serialize>(
+    path: &Path,
+    kind: ParamsKind,
+    key: &ParamsKey,
+    digest: Vec<u8>,
+    value: &T,
+) -> Result<(), ParamsError> {
+    let encoded = bincode::serialize(value)?;
+    let header = ParamsHeader {
+        version: PARAMS_FORMAT_VERSION,
+        kind,
+        key: key.clone(),
+        digest,
+        checksum: Keccak256::digest(&encoded).to_vec(),
+    };
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut writer = BufWriter::new(File::create(path)?);
+    bincode::serialize_into(&mut writer, &header)?;
+    writer.write_all(&encoded)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Loads a value saved by `save`, and returns it with the digest of its
+/// header.
+fn load<T: DeserializeOwned>(
+    path: &Path,
+    kind: ParamsKind,
+    key: &ParamsKey,
+) -> Result<(T, Vec<u8>), ParamsError> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header: ParamsHeader = bincode::deserialize_from(&mut reader)?;
+    if header.version != PARAMS_FORMAT_VERSION {
+        return Err(ParamsError::WrongVersion(header.version));
+    }
+    if header.kind != kind {
+        return Err(ParamsError::WrongKind(header.kind));
+    }
+    if &header.key != key {
+        return Err(ParamsError::WrongKey(header.key));
+    }
+
+    let mut encoded = Vec::new();
+    reader.read_to_end(&mut encoded)?;
+    if Keccak256::digest(&encoded)[..] != header.checksum[..] {
+        return Err(ParamsError::WrongChecksum);
+    }
+
+    Ok((bincode::deserialize(&encoded)?, header.digest))
+}
+
+pub fn save_public_params<G1, G2>(
+    path: &Path,
+    key: &ParamsKey,
+    pp: &GamePublicParams<G1, G2>,
+) -> Result<(), ParamsError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    save(
+        path,
+        ParamsKind::PublicParams,
+        key,
+        element_bytes(pp.digest()),
+        pp,
+    )
+}
+
+/// Loads public parameters saved by `save_public_params` with the same key.
+///
+/// The key holds the digest of the circuit as synthesized by the caller, so
+/// parameters generated for another circuit are rejected. The digest of the
+/// public parameters is not checked: they do not carry it and it would be
+/// recomputed from the file itself. Tampered parameters only give proofs which
+/// fail against the digest of the verifier.
+pub fn load_public_params<G1, G2>(
+    path: &Path,
+    key: &ParamsKey,
+) -> Result<GamePublicParams<G1, G2>, ParamsError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    let (pp, _) = load(path, ParamsKind::PublicParams, key)?;
+
+    Ok(pp)
+}
+
+/// Saves a prover key generated from the public parameters of digest
+/// `pp_digest`.
+pub fn save_prover_key<G1, G2, S1, S2>(
+    path: &Path,
+    key: &ParamsKey,
+    pp_digest: G1::Scalar,
+    pk: &GameProverKey<G1, G2, S1, S2>,
+) -> Result<(), ParamsError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    save(
+        path,
+        ParamsKind::ProverKey,
+        key,
+        element_bytes(pp_digest),
+        pk,
+    )
+}
+
+/// Loads a prover key saved by `save_prover_key`, checking that it was
+/// generated from the public parameters of digest `pp_digest`.
+pub fn load_prover_key<G1, G2, S1, S2>(
+    path: &Path,
+    key: &ParamsKey,
+    pp_digest: G1::Scalar,
+) -> Result<GameProverKey<G1, G2, S1, S2>, ParamsError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    let (pk, digest) = load(path, ParamsKind::ProverKey, key)?;
+    if element_bytes(pp_digest) != digest {
+        return Err(ParamsError::WrongDigest);
+    }
+
+    Ok(pk)
+}
+
+/// Saves a verifier key generated from the public parameters of digest
+/// `pp_digest`.
+pub fn save_verifier_key<G1, G2, S1, S2>(
+    path: &Path,
+    key: &ParamsKey,
+    pp_digest: G1::Scalar,
+    vk: &GameVerifierKey<G1, G2, S1, S2>,
+) -> Result<(), ParamsError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    save(
+        path,
+        ParamsKind::VerifierKey,
+        key,
+        element_bytes(pp_digest),
+        vk,
+    )
+}
+
+/// Loads a verifier key saved by `save_verifier_key`, and returns it with the
+/// digest of the public parameters it was generated from. A verifier without
+/// the public parameters compares that digest with a published one.
+#[allow(clippy::type_complexity)]
+pub fn load_verifier_key<G1, G2, S1, S2>(
+    path: &Path,
+    key: &ParamsKey,
+) -> Result<(GameVerifierKey<G1, G2, S1, S2>, G1::Scalar), ParamsError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    let (vk, digest) = load(path, ParamsKind::VerifierKey, key)?;
+    let digest = element_from_bytes(&digest).ok_or(ParamsError::WrongDigest)?;
+
+    Ok((vk, digest))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use nova_snark::traits::Group;
+
+    use crate::{circuit::GameConfig, prover::GameProver};
+
+    use super::{
+        circuit_digest, load_prover_key, load_public_params, load_verifier_key, save_prover_key,
+        save_public_params, save_verifier_key, ParamsError, ParamsKey, ParamsKind,
+    };
+
+    type E1 = pasta_curves::pallas::Point;
+    type E2 = pasta_curves::vesta::Point;
+    type EE1 = nova_snark::provider::ipa_pc::EvaluationEngine<E1>;
+    type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
+    type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>;
+    type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>;
+
+    #[test]
+    fn test_params() {
+        let dir = std::env::temp_dir().join(format!("nova-2048-params-{}", std::process::id()));
+        let config = GameConfig::default();
+        let key = ParamsKey::new::<E1>(config, 1);
+
+        let prover = GameProver::<E1, E2>::setup(config, 1);
+        let pp = prover.public_params();
+        let pp_path = key.path(&dir, ParamsKind::PublicParams);
+        save_public_params(&pp_path, &key, pp).unwrap();
+
+        let loaded = load_public_params::<E1, E2>(&pp_path, &key).unwrap();
+        assert_eq!(loaded.digest(), pp.digest());
+
+        let other = ParamsKey::new::<E1>(config, 2);
+        assert_ne!(
+            other.file_name(ParamsKind::PublicParams),
+            key.file_name(ParamsKind::PublicParams)
+        );
+        let res = load_public_params::<E1, E2>(&pp_path, &other);
+        assert!(matches!(res, Err(ParamsError::WrongKey(k)) if k == key));
+
+        let (pk, vk) = prover.compressed_keys::<S1, S2>().unwrap();
+        let pk_path = key.path(&dir, ParamsKind::ProverKey);
+        let vk_path = key.path(&dir, ParamsKind::VerifierKey);
+        save_prover_key(&pk_path, &key, pp.digest(), &pk).unwrap();
+        save_verifier_key(&vk_path, &key, pp.digest(), &vk).unwrap();
+
+        assert!(load_prover_key::<E1, E2, S1, S2>(&pk_path, &key, pp.digest()).is_ok());
+        let res = load_prover_key::<E1, E2, S1, S2>(&pk_path, &key, <E1 as Group>::Scalar::one());
+        assert!(matches!(res, Err(ParamsError::WrongDigest)));
+        let res = load_verifier_key::<E1, E2, S1, S2>(&pk_path, &key);
+        assert!(matches!(
+            res,
+            Err(ParamsError::WrongKind(ParamsKind::ProverKey))
+        ));
+        let (_, digest) = load_verifier_key::<E1, E2, S1, S2>(&vk_path, &key).unwrap();
+        assert_eq!(digest, pp.digest());
+
+        // Flip a bit of the value.
+        let mut bytes = fs::read(&vk_path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        fs::write(&vk_path, bytes).unwrap();
+        let res = load_verifier_key::<E1, E2, S1, S2>(&vk_path, &key);
+        assert!(matches!(res, Err(ParamsError::WrongChecksum)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_circuit_digest() {
+        let config = GameConfig::default();
+        let digest = circuit_digest::<E1>(config, 1);
+        assert_eq!(digest, circuit_digest::<E1>(config, 1));
+        assert_ne!(digest, circuit_digest::<E1>(config, 2));
+        assert_ne!(digest, circuit_digest::<E2>(config, 1));
+
+        let private = GameConfig {
+            private_board: true,
+            ..config
+        };
+        assert_ne!(digest, circuit_digest::<E1>(private, 1));
+    }
+}
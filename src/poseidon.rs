@@ -0,0 +1,112 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+use generic_array::typenum::{U16, U2, U24, U4, U8};
+use neptune::{circuit2::poseidon_hash_allocated, poseidon::PoseidonConstants, Arity, Poseidon};
+
+/// The longest preimage that can be hashed.
+pub const MAX_PREIMAGE_LEN: usize = 24;
+
+type ConstantsCache = HashMap<(TypeId, TypeId, usize), Arc<dyn Any + Send + Sync>>;
+
+/// Returns the constants for hashing `len` elements with arity `A`.
+///
+/// Generating the constants costs much more than hashing, so they are
+/// generated once and shared.
+fn constants<F: PrimeField, A: Arity<F> + Send + Sync>(len: usize) -> Arc<PoseidonConstants<F, A>> {
+    static CACHE: OnceLock<Mutex<ConstantsCache>> = OnceLock::new();
+
+    let key = (TypeId::of::<F>(), TypeId::of::<A>(), len);
+    let mut cache = CACHE.get_or_init(Default::default).lock().unwrap();
+    let constants = cache
+        .entry(key)
+        .or_insert_with(|| Arc::new(PoseidonConstants::<F, A>::new_constant_length(len)))
+        .clone();
+
+    constants.downcast().unwrap()
+}
+
+/// Hashes `preimage` with a constant-length Poseidon, in the circuit.
+///
+/// The length of the preimage is part of the domain tag, so preimages of
+/// different lengths never collide.
+pub fn hash_circuit<F: PrimeField, CS: ConstraintSystem<F>>(
+    cs: CS,
+    preimage: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+    fn hash_with<F: PrimeField, A: Arity<F> + Send + Sync, CS: ConstraintSystem<F>>(
+        cs: CS,
+        preimage: &[AllocatedNum<F>],
+    ) -> Result<AllocatedNum<F>, SynthesisError> {
+        let constants = constants::<F, A>(preimage.len());
+        poseidon_hash_allocated(cs, preimage.to_vec(), &constants)
+    }
+
+    match preimage.len() {
+        1..=2 => hash_with::<F, U2, CS>(cs, preimage),
+        3..=4 => hash_with::<F, U4, CS>(cs, preimage),
+        5..=8 => hash_with::<F, U8, CS>(cs, preimage),
+        9..=16 => hash_with::<F, U16, CS>(cs, preimage),
+        17..=MAX_PREIMAGE_LEN => hash_with::<F, U24, CS>(cs, preimage),
+        len => panic!("cannot hash a preimage of length {}", len),
+    }
+}
+
+/// Hashes `preimage` with a constant-length Poseidon, out of the circuit.
+/// Returns the same value as `hash_circuit`.
+pub fn hash<F: PrimeField>(preimage: &[F]) -> F {
+    fn hash_with<F: PrimeField, A: Arity<F> + Send + Sync>(preimage: &[F]) -> F {
+        let constants = constants::<F, A>(preimage.len());
+        Poseidon::new_with_preimage(preimage, &constants).hash()
+    }
+
+    match preimage.len() {
+        1..=2 => hash_with::<F, U2>(preimage),
+        3..=4 => hash_with::<F, U4>(preimage),
+        5..=8 => hash_with::<F, U8>(preimage),
+        9..=16 => hash_with::<F, U16>(preimage),
+        17..=MAX_PREIMAGE_LEN => hash_with::<F, U24>(preimage),
+        len => panic!("cannot hash a preimage of length {}", len),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+    use blstrs::Scalar as Fr;
+
+    use super::{hash, hash_circuit, MAX_PREIMAGE_LEN};
+
+    #[test]
+    fn test_hash_circuit() {
+        let mut digests = vec![];
+        for len in [1, 2, 3, 4, 5, 9, 17, MAX_PREIMAGE_LEN] {
+            let preimage: Vec<_> = (0..len as u64).map(Fr::from).collect();
+
+            let mut cs = TestConstraintSystem::<Fr>::new();
+            let mut preimage_vars = vec![];
+            for (i, x) in preimage.iter().enumerate() {
+                preimage_vars.push(
+                    AllocatedNum::alloc(cs.namespace(|| format!("preimage_{i}")), || Ok(*x))
+                        .unwrap(),
+                );
+            }
+
+            let digest = hash_circuit(&mut cs, &preimage_vars).unwrap();
+            assert!(cs.is_satisfied());
+            assert_eq!(digest.get_value().unwrap(), hash(&preimage));
+
+            digests.push(hash(&preimage));
+        }
+
+        // Zero-padding a preimage does not produce the same digest.
+        assert_ne!(hash(&[Fr::from(0)]), hash(&[Fr::from(0), Fr::from(0)]));
+        digests.dedup();
+        assert_eq!(digests.len(), 8);
+    }
+}
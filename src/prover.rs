@@ -0,0 +1,658 @@
+use std::{fmt, path::Path};
+
+use ff::Field;
+use nova_snark::{
+    errors::NovaError,
+    traits::{
+        circuit::TrivialCircuit,
+        snark::{default_ck_hint, RelaxedR1CSSNARKTrait},
+        Group,
+    },
+    CompressedSNARK, ProverKey, PublicParams, RecursiveSNARK, VerifierKey,
+};
+
+use crate::{
+    circuit::{Game2048Circuit, GameConfig},
+    commitment::StartOpening,
+    game::{Direction, Game},
+    history::HISTORY_DEPTH,
+    params::{load_public_params, save_public_params, ParamsError, ParamsKey, ParamsKind},
+    poseidon::hash,
+};
+
+/// The public parameters of a game, with a trivial secondary circuit.
+pub type GamePublicParams<G1, G2> = PublicParams<
+    G1,
+    G2,
+    Game2048Circuit<<G1 as Group>::Scalar>,
+    TrivialCircuit<<G2 as Group>::Scalar>,
+>;
+
+pub type GameRecursiveSNARK<G1, G2> = RecursiveSNARK<
+    G1,
+    G2,
+    Game2048Circuit<<G1 as Group>::Scalar>,
+    TrivialCircuit<<G2 as Group>::Scalar>,
+>;
+
+pub type GameCompressedSNARK<G1, G2, S1, S2> = CompressedSNARK<
+    G1,
+    G2,
+    Game2048Circuit<<G1 as Group>::Scalar>,
+    TrivialCircuit<<G2 as Group>::Scalar>,
+    S1,
+    S2,
+>;
+
+pub type GameProverKey<G1, G2, S1, S2> = ProverKey<
+    G1,
+    G2,
+    Game2048Circuit<<G1 as Group>::Scalar>,
+    TrivialCircuit<<G2 as Group>::Scalar>,
+    S1,
+    S2,
+>;
+
+pub type GameVerifierKey<G1, G2, S1, S2> = VerifierKey<
+    G1,
+    G2,
+    Game2048Circuit<<G1 as Group>::Scalar>,
+    TrivialCircuit<<G2 as Group>::Scalar>,
+    S1,
+    S2,
+>;
+
+/// The reason a game cannot be proven.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProveError {
+    /// There is no move to prove.
+    NoMoves,
+
+    /// A step does not have the number of moves of the public parameters.
+    WrongStepLength { moves: usize, moves_per_step: usize },
+
+    /// Nova failed to prove a step.
+    Nova(NovaError),
+
+    /// The moves append more boards than the board history holds, see
+    /// `GameConfig::board_history`.
+    HistoryFull,
+
+    /// The game has other rules than the public parameters.
+    WrongConfig {
+        expected: GameConfig,
+        actual: GameConfig,
+    },
+
+    /// The prover has no blinding seed in private-board or claim mode, see
+    /// `GameProver::with_blinding_seed`.
+    MissingBlindingSeed,
+
+    /// The thread proving the game in the background panicked, see
+    /// `BackgroundProver`.
+    ThreadPanicked,
+}
+
+impl fmt::Display for ProveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProveError::NoMoves => write!(f, "there is no move to prove"),
+            ProveError::WrongStepLength {
+                moves,
+                moves_per_step,
+            } => write!(
+                f,
+                "a step of {} moves does not fit parameters of {} moves per step",
+                moves, moves_per_step
+            ),
+            ProveError::Nova(e) => write!(f, "the proof failed: {}", e),
+            ProveError::HistoryFull => write!(
+                f,
+                "the board history holds at most 2^{} boards",
+                HISTORY_DEPTH
+            ),
+            ProveError::WrongConfig { expected, actual } => write!(
+                f,
+                "a game of rules {:?} does not fit parameters of rules {:?}",
+                actual, expected
+            ),
+            ProveError::MissingBlindingSeed => {
+                write!(f, "the commitments need a blinding seed")
+            }
+            ProveError::ThreadPanicked => write!(f, "the proving thread panicked"),
+        }
+    }
+}
+
+impl std::error::Error for ProveError {}
+
+/// Proves games played with one `GameConfig` and a fixed number of moves
+/// per step.
+///
+/// The circuits are built one step at a time while the moves are consumed,
+/// so the moves of a long game never have to be held in memory.
+pub struct GameProver<G1, G2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    pp: GamePublicParams<G1, G2>,
+
+    /// The key of the public parameters on disk, which holds the config and
+    /// the moves per step.
+    key: ParamsKey,
+
+    /// The secret from which the blindings of the commitments are derived in
+    /// private-board and claim modes, which cannot be proven without it.
+    blinding_seed: Option<G1::Scalar>,
+
+    /// The score and max tile thresholds in claim mode.
+    claim: (u64, u64),
+}
+
+impl<G1, G2> GameProver<G1, G2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    /// Generates the public parameters of steps of `moves_per_step` moves.
+    pub fn setup(config: GameConfig, moves_per_step: usize) -> Self {
+        assert!(moves_per_step > 0, "a step needs at least one move");
+
+        Self::setup_with_key(ParamsKey::new::<G1>(config, moves_per_step))
+    }
+
+    fn setup_with_key(key: ParamsKey) -> Self {
+        let circuit_primary = Game2048Circuit::blank(key.moves_per_step, key.config);
+        let pp = PublicParams::setup(
+            &circuit_primary,
+            &TrivialCircuit::default(),
+            &*default_ck_hint(),
+            &*default_ck_hint(),
+        );
+
+        Self::with_key(pp, key)
+    }
+
+    /// Loads the public parameters of `config` and `moves_per_step` from `dir`,
+    /// or generates them and saves them there if there are none yet.
+    pub fn load_or_setup(
+        dir: &Path,
+        config: GameConfig,
+        moves_per_step: usize,
+    ) -> Result<Self, ParamsError> {
+        assert!(moves_per_step > 0, "a step needs at least one move");
+
+        let key = ParamsKey::new::<G1>(config, moves_per_step);
+        let path = key.path(dir, ParamsKind::PublicParams);
+        if path.exists() {
+            let pp = load_public_params(&path, &key)?;
+            return Ok(Self::with_key(pp, key));
+        }
+
+        let prover = Self::setup_with_key(key);
+        save_public_params(&path, &prover.key, &prover.pp)?;
+        Ok(prover)
+    }
+
+    /// Uses public parameters generated by `setup` with the same arguments.
+    pub fn from_public_params(
+        pp: GamePublicParams<G1, G2>,
+        config: GameConfig,
+        moves_per_step: usize,
+    ) -> Self {
+        assert!(moves_per_step > 0, "a step needs at least one move");
+
+        Self::with_key(pp, ParamsKey::new::<G1>(config, moves_per_step))
+    }
+
+    fn with_key(pp: GamePublicParams<G1, G2>, key: ParamsKey) -> Self {
+        Self {
+            pp,
+            key,
+            blinding_seed: None,
+            claim: (0, 0),
+        }
+    }
+
+    /// Derives the blindings of the commitments from `seed`, which must be
+    /// secret for the commitments to hide anything. Required in private-board
+    /// and claim modes.
+    pub fn with_blinding_seed(mut self, seed: G1::Scalar) -> Self {
+        self.blinding_seed = Some(seed);
+        self
+    }
+
+    /// Sets the thresholds of the claim in claim mode, see `GameConfig::claim`.
+    pub fn with_claim(mut self, score_threshold: u64, max_tile_threshold: u64) -> Self {
+        self.claim = (score_threshold, max_tile_threshold);
+        self
+    }
+
+    pub fn public_params(&self) -> &GamePublicParams<G1, G2> {
+        &self.pp
+    }
+
+    pub fn config(&self) -> GameConfig {
+        self.key.config
+    }
+
+    pub fn moves_per_step(&self) -> usize {
+        self.key.moves_per_step
+    }
+
+    /// Returns the key of the public parameters on disk, see `params`.
+    pub fn params_key(&self) -> ParamsKey {
+        self.key.clone()
+    }
+
+    /// Returns the blinding of the commitment in `z` before step `step`.
+    fn blinding(&self, step: usize) -> Result<G1::Scalar, ProveError> {
+        let seed = self.blinding_seed.ok_or(ProveError::MissingBlindingSeed)?;
+        Ok(hash(&[seed, G1::Scalar::from(step as u64)]))
+    }
+
+    /// Returns the opening of the `z0` of a proof started from `game` in
+    /// private-board and claim modes, for the verifier to check that the proof
+    /// starts a new game, see `StartOpening`.
+    pub fn start_opening(
+        &self,
+        game: &Game<G1::Scalar>,
+    ) -> Result<StartOpening<G1::Scalar>, ProveError> {
+        Ok(StartOpening {
+            board: game.board,
+            blinding: self.blinding(0)?,
+        })
+    }
+
+    /// Returns the state `z` of `game` before step `step`, as the circuit
+    /// carries it with this prover's config.
+    pub fn z(&self, game: &Game<G1::Scalar>, step: usize) -> Result<Vec<G1::Scalar>, ProveError> {
+        let z = if self.key.config.claim {
+            game.claim_z(self.blinding(step)?, self.claim.0, self.claim.1)
+        } else if self.key.config.private_board {
+            game.private_z(self.blinding(step)?)
+        } else if self.key.config.packed_board {
+            game.packed_z()
+        } else {
+            game.state().to_z()
+        };
+
+        Ok(z)
+    }
+
+    /// Returns the circuit of step `step`, which plays `moves` from `game`.
+    /// There must be `moves_per_step` moves.
+    pub fn step_circuit(
+        &self,
+        game: &Game<G1::Scalar>,
+        step: usize,
+        moves: &[Direction],
+    ) -> Result<Game2048Circuit<G1::Scalar>, ProveError> {
+        if moves.len() != self.key.moves_per_step {
+            return Err(ProveError::WrongStepLength {
+                moves: moves.len(),
+                moves_per_step: self.key.moves_per_step,
+            });
+        }
+
+        let directions: Vec<_> = moves.iter().map(|d| d.to_bits()).collect();
+        let mut circuit = Game2048Circuit::with_config(&directions, self.key.config);
+
+        if self.key.config.private_board {
+            let input = game.private_input(self.blinding(step)?, self.blinding(step + 1)?);
+            circuit = circuit.with_private_input(input);
+        }
+
+        if self.key.config.board_history {
+            let paths = game.history_paths(moves).ok_or(ProveError::HistoryFull)?;
+            circuit = circuit.with_history_paths(paths);
+        }
+
+        Ok(circuit)
+    }
+
+    /// Starts a proof of moves played from `game`, to be folded one step at a
+    /// time with `prove_step`. The game must have the rules of the prover.
+    pub fn start(&self, game: Game<G1::Scalar>) -> Result<IncrementalProof<G1, G2>, ProveError> {
+        if game.config != self.key.config {
+            return Err(ProveError::WrongConfig {
+                expected: self.key.config,
+                actual: game.config,
+            });
+        }
+
+        Ok(IncrementalProof {
+            snark: None,
+            num_steps: 0,
+            z0_primary: self.z(&game, 0)?,
+            start: game.clone(),
+            moves: vec![],
+            game,
+        })
+    }
+
+    /// Folds a step of `moves` into `proof`. There must be `moves_per_step`
+    /// moves.
+    pub fn prove_step(
+        &self,
+        proof: &mut IncrementalProof<G1, G2>,
+        moves: &[Direction],
+    ) -> Result<(), ProveError> {
+        let circuit_primary = self.step_circuit(&proof.game, proof.num_steps, moves)?;
+        let circuit_secondary = TrivialCircuit::default();
+        let snark = match proof.snark.as_mut() {
+            Some(snark) => snark,
+            None => proof.snark.insert(
+                RecursiveSNARK::new(
+                    &self.pp,
+                    &circuit_primary,
+                    &circuit_secondary,
+                    &proof.z0_primary,
+                    &[G2::Scalar::ZERO],
+                )
+                .map_err(ProveError::Nova)?,
+            ),
+        };
+        snark
+            .prove_step(&self.pp, &circuit_primary, &circuit_secondary)
+            .map_err(ProveError::Nova)?;
+
+        for direction in moves {
+            proof.game.play(*direction);
+        }
+        proof.moves.extend_from_slice(moves);
+        proof.num_steps += 1;
+
+        Ok(())
+    }
+
+    /// Ends a proof, which must have at least one step.
+    pub fn finish(&self, proof: IncrementalProof<G1, G2>) -> Result<GameProof<G1, G2>, ProveError> {
+        let snark = proof.snark.ok_or(ProveError::NoMoves)?;
+        let zn_primary = self.z(&proof.game, proof.num_steps)?;
+
+        Ok(GameProof {
+            snark,
+            num_steps: proof.num_steps,
+            z0_primary: proof.z0_primary,
+            zn_primary,
+            game: proof.game,
+        })
+    }
+
+    /// Proves `moves` played from `game`, `moves_per_step` moves per step. The
+    /// last step is padded with `Direction::Noop`.
+    pub fn prove<I>(
+        &self,
+        game: Game<G1::Scalar>,
+        moves: I,
+    ) -> Result<GameProof<G1, G2>, ProveError>
+    where
+        I: IntoIterator<Item = Direction>,
+    {
+        let mut proof = self.start(game)?;
+        let mut moves = moves.into_iter();
+        loop {
+            let mut chunk: Vec<_> = moves.by_ref().take(self.key.moves_per_step).collect();
+            if chunk.is_empty() {
+                break;
+            }
+            chunk.resize(self.key.moves_per_step, Direction::Noop);
+
+            self.prove_step(&mut proof, &chunk)?;
+        }
+
+        self.finish(proof)
+    }
+
+    /// Generates the keys of compressed proofs.
+    #[allow(clippy::type_complexity)]
+    pub fn compressed_keys<S1, S2>(
+        &self,
+    ) -> Result<
+        (
+            GameProverKey<G1, G2, S1, S2>,
+            GameVerifierKey<G1, G2, S1, S2>,
+        ),
+        ProveError,
+    >
+    where
+        S1: RelaxedR1CSSNARKTrait<G1>,
+        S2: RelaxedR1CSSNARKTrait<G2>,
+    {
+        CompressedSNARK::setup(&self.pp).map_err(ProveError::Nova)
+    }
+
+    /// Compresses a proof, see `compressed_keys`.
+    pub fn compress<S1, S2>(
+        &self,
+        pk: &GameProverKey<G1, G2, S1, S2>,
+        proof: &GameProof<G1, G2>,
+    ) -> Result<CompressedGameProof<G1, G2, S1, S2>, ProveError>
+    where
+        S1: RelaxedR1CSSNARKTrait<G1>,
+        S2: RelaxedR1CSSNARKTrait<G2>,
+    {
+        let snark = CompressedSNARK::prove(&self.pp, pk, &proof.snark).map_err(ProveError::Nova)?;
+
+        Ok(CompressedGameProof {
+            snark,
+            num_steps: proof.num_steps,
+            z0_primary: proof.z0_primary.clone(),
+            zn_primary: proof.zn_primary.clone(),
+        })
+    }
+}
+
+/// A proof being folded, see `GameProver::start`.
+pub struct IncrementalProof<G1, G2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    /// `None` until the first step.
+    pub(crate) snark: Option<GameRecursiveSNARK<G1, G2>>,
+
+    pub(crate) num_steps: usize,
+
+    pub(crate) z0_primary: Vec<G1::Scalar>,
+
+    /// The game before the first step.
+    pub(crate) start: Game<G1::Scalar>,
+
+    /// The moves of the folded steps, with their padding.
+    pub(crate) moves: Vec<Direction>,
+
+    /// The game after the folded steps.
+    pub(crate) game: Game<G1::Scalar>,
+}
+
+impl<G1, G2> IncrementalProof<G1, G2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    pub fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    /// The game before the first step.
+    pub fn start(&self) -> &Game<G1::Scalar> {
+        &self.start
+    }
+
+    /// The moves of the folded steps, with their padding.
+    pub fn moves(&self) -> &[Direction] {
+        &self.moves
+    }
+
+    pub fn game(&self) -> &Game<G1::Scalar> {
+        &self.game
+    }
+}
+
+/// A recursive proof of a game, with its public inputs and outputs.
+pub struct GameProof<G1, G2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    pub snark: GameRecursiveSNARK<G1, G2>,
+
+    pub num_steps: usize,
+
+    pub z0_primary: Vec<G1::Scalar>,
+
+    /// The final state `z`, as the proof outputs it.
+    pub zn_primary: Vec<G1::Scalar>,
+
+    /// The game after the proven moves. It knows the private parts of the
+    /// state, so it stays with the prover.
+    pub game: Game<G1::Scalar>,
+}
+
+impl<G1, G2> GameProof<G1, G2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+{
+    /// Verifies the proof, and returns its final state `z`.
+    pub fn verify(&self, pp: &GamePublicParams<G1, G2>) -> Result<Vec<G1::Scalar>, NovaError> {
+        let (zn_primary, _) =
+            self.snark
+                .verify(pp, self.num_steps, &self.z0_primary, &[G2::Scalar::ZERO])?;
+
+        Ok(zn_primary)
+    }
+}
+
+/// A compressed proof of a game, with its public inputs and outputs.
+pub struct CompressedGameProof<G1, G2, S1, S2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    pub snark: GameCompressedSNARK<G1, G2, S1, S2>,
+
+    pub num_steps: usize,
+
+    pub z0_primary: Vec<G1::Scalar>,
+
+    /// The final state `z`, as the proof outputs it.
+    pub zn_primary: Vec<G1::Scalar>,
+}
+
+impl<G1, G2, S1, S2> CompressedGameProof<G1, G2, S1, S2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    /// Verifies the proof, and returns its final state `z`.
+    pub fn verify(
+        &self,
+        vk: &GameVerifierKey<G1, G2, S1, S2>,
+    ) -> Result<Vec<G1::Scalar>, NovaError> {
+        let (zn_primary, _) =
+            self.snark
+                .verify(vk, self.num_steps, &self.z0_primary, &[G2::Scalar::ZERO])?;
+
+        Ok(zn_primary)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nova_snark::traits::Group;
+
+    use crate::{
+        circuit::GameConfig,
+        game::{Direction, Game},
+    };
+
+    use super::{GameProver, ProveError};
+
+    type E1 = pasta_curves::pallas::Point;
+    type E2 = pasta_curves::vesta::Point;
+    type F1 = <E1 as Group>::Scalar;
+    type EE1 = nova_snark::provider::ipa_pc::EvaluationEngine<E1>;
+    type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
+    type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>;
+    type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>;
+
+    #[rustfmt::skip]
+    const BOARD: [u64; 16] = [
+        0, 0, 2, 0,
+        0, 4, 0, 0,
+        0, 0, 0, 0,
+        0, 0, 0, 0,
+    ];
+
+    #[test]
+    fn test_game_prover() {
+        let config = GameConfig::default();
+        let prover = GameProver::<E1, E2>::setup(config, 2);
+        let game = Game::<F1>::new(BOARD, config);
+
+        let moves = [Direction::Left, Direction::Down]
+            .into_iter()
+            .cycle()
+            .take(4);
+        let proof = prover.prove(game.clone(), moves).unwrap();
+        assert_eq!(proof.num_steps, 2);
+        assert_eq!(
+            proof.verify(prover.public_params()),
+            Ok(proof.zn_primary.clone())
+        );
+        assert_eq!(proof.zn_primary, proof.game.state().to_z());
+
+        let (pk, vk) = prover.compressed_keys::<S1, S2>().unwrap();
+        let compressed = prover.compress(&pk, &proof).unwrap();
+        assert_eq!(compressed.verify(&vk), Ok(proof.zn_primary.clone()));
+
+        // The last step is padded with a no-op.
+        let proof = prover.prove(game.clone(), [Direction::Left; 3]).unwrap();
+        assert_eq!(proof.num_steps, 2);
+        assert!(proof.verify(prover.public_params()).is_ok());
+        let mut played = game.clone();
+        for _ in 0..3 {
+            played.play(Direction::Left);
+        }
+        assert_eq!(proof.zn_primary, played.state().to_z());
+
+        let res = prover.prove(game.clone(), []);
+        assert!(matches!(res, Err(ProveError::NoMoves)));
+
+        let res = prover.step_circuit(&game, 0, &[Direction::Left; 3]);
+        assert!(matches!(
+            res,
+            Err(ProveError::WrongStepLength {
+                moves: 3,
+                moves_per_step: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn test_blinding_seed() {
+        let config = GameConfig {
+            private_board: true,
+            ..GameConfig::default()
+        };
+        let prover = GameProver::<E1, E2>::setup(config, 1);
+        let game = Game::<F1>::new(BOARD, config);
+        let res = prover.prove(game.clone(), [Direction::Left]);
+        assert!(matches!(res, Err(ProveError::MissingBlindingSeed)));
+
+        let prover = prover.with_blinding_seed(F1::from(7));
+        let proof = prover.prove(game, [Direction::Left]).unwrap();
+        assert_eq!(
+            proof.verify(prover.public_params()),
+            Ok(proof.zn_primary.clone())
+        );
+    }
+}
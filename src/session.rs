@@ -0,0 +1,127 @@
+//! A game being played, with the moves it was played with.
+//!
+//! `Session` records every move it plays, so that the game can be proved
+//! once it ends, see `GameProver::prove`.
+
+use std::fmt::Write;
+
+use ff::PrimeField;
+
+use crate::game::{Direction, Game};
+
+#[derive(Debug, Clone)]
+pub struct Session<F: PrimeField> {
+    start: Game<F>,
+    game: Game<F>,
+    moves: Vec<Direction>,
+}
+
+impl<F: PrimeField> Session<F> {
+    pub fn new(game: Game<F>) -> Self {
+        Self {
+            start: game.clone(),
+            game,
+            moves: vec![],
+        }
+    }
+
+    /// The game before the first move.
+    pub fn start(&self) -> &Game<F> {
+        &self.start
+    }
+
+    /// The game after the last move.
+    pub fn game(&self) -> &Game<F> {
+        &self.game
+    }
+
+    pub fn moves(&self) -> &[Direction] {
+        &self.moves
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.game.game_over
+    }
+
+    /// Plays and records a move. A move after the end of the game, or a
+    /// no-op, is neither played nor recorded, and `false` is returned.
+    ///
+    /// A move which does not change the board is recorded, since it still
+    /// goes into the transcript.
+    pub fn play(&mut self, direction: Direction) -> bool {
+        if self.is_over() || direction == Direction::Noop {
+            return false;
+        }
+
+        self.game.play(direction);
+        self.moves.push(direction);
+
+        true
+    }
+
+    /// Returns the moves one per line, as `Direction::from_str` parses them.
+    pub fn move_log(&self) -> String {
+        let mut log = String::new();
+        for direction in &self.moves {
+            writeln!(log, "{}", direction).unwrap();
+        }
+
+        log
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use blstrs::Scalar as Fr;
+
+    use crate::{
+        circuit::GameConfig,
+        game::{Direction, Game},
+    };
+
+    use super::Session;
+
+    #[test]
+    fn test_session() {
+        let config = GameConfig::default();
+        let mut session = Session::new(Game::<Fr>::new([2; 16], config));
+        let moves = [
+            Direction::Left,
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+        ];
+        for direction in moves {
+            assert!(session.play(direction));
+        }
+        assert!(!session.play(Direction::Noop));
+        assert_eq!(session.moves(), moves);
+
+        let mut game = session.start().clone();
+        for direction in moves {
+            game.play(direction);
+        }
+        assert_eq!(game.state(), session.game().state());
+
+        let parsed: Vec<Direction> = session
+            .move_log()
+            .lines()
+            .map(|line| line.parse().unwrap())
+            .collect();
+        assert_eq!(parsed, moves);
+
+        #[rustfmt::skip]
+        let board = [
+            2, 4, 2, 4,
+            4, 2, 4, 2,
+            2, 4, 2, 4,
+            4, 2, 4, 2,
+        ];
+        let mut game = Game::<Fr>::new(board, config);
+        game.game_over = true;
+        let mut session = Session::new(game);
+        assert!(session.is_over());
+        assert!(!session.play(Direction::Left));
+        assert!(session.moves().is_empty());
+    }
+}
@@ -84,10 +84,10 @@ mod test {
         let two = Fr::from(2);
         let four = Fr::from(4);
 
-        let line_0 = vec![zero, zero, four, zero];
-        let line_1 = vec![zero, two, two, zero];
-        let line_2 = vec![zero, two, four, four];
-        let line_3 = vec![two, zero, zero, four];
+        let line_0 = [zero, zero, four, zero];
+        let line_1 = [zero, two, two, zero];
+        let line_2 = [zero, two, four, four];
+        let line_3 = [two, zero, zero, four];
 
         let mut cs = TestConstraintSystem::<Fr>::new();
 
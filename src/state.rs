@@ -0,0 +1,161 @@
+use crate::merge_counter::NUM_LEVELS;
+
+/// The state `z` that `Game2048Circuit` carries from one step to the next.
+///
+/// In private-board mode `z` carries a commitment to the state in place of
+/// everything but the player, the nonce, the score and the max tile, see
+/// `to_private_z`. In packed-board mode it carries the board packed into one
+/// element, see `to_packed_z`.
+///
+/// `T` is a field element out of the circuit, and an `AllocatedNum` in the circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameState<T> {
+    /// The 16 cells of the board, row by row.
+    pub board: Vec<T>,
+
+    /// The running hash of the moves, see `Transcript`.
+    pub transcript: T,
+
+    /// The identifier of the player, see `player_id`. It never changes, and
+    /// it is mixed into the seed of every spawned number, so a proof only
+    /// verifies against the `z0` of the player who played the game.
+    pub player: T,
+
+    /// A nonce issued by the verifier for this game session. It never changes,
+    /// and it is mixed into the seed of every spawned number, so a proof cannot
+    /// be resubmitted for another session.
+    pub nonce: T,
+
+    /// The score, the sum of all the numbers created by merges.
+    pub score: T,
+
+    /// The number of moves played, see `GameConfig::count_stuck_moves`.
+    pub moves: T,
+
+    /// The maximum tile ever seen on the board.
+    pub max_tile: T,
+
+    /// 1 once `max_tile` reached `GameConfig::target`, 0 before.
+    pub reached: T,
+
+    /// The value of `moves` after the move which reached the target.
+    pub reached_at: T,
+
+    /// 1 once no move can change the board, 0 before. It never goes back to
+    /// 0, and the moves after it is set leave the state untouched.
+    pub game_over: T,
+
+    /// The number of merges which created each tile level, 4s first, see
+    /// `MergeCounter`.
+    pub merges: Vec<T>,
+
+    /// The root of the Merkle tree of the boards after every counted move,
+    /// see `GameConfig::board_history`. Always 0 if it is not enabled.
+    pub history: T,
+}
+
+impl<T: Clone> GameState<T> {
+    /// The number of elements in `z`.
+    pub const ARITY: usize = 26 + NUM_LEVELS;
+
+    pub fn from_z(z: &[T]) -> Self {
+        assert_eq!(z.len(), Self::ARITY);
+
+        Self {
+            board: z[..16].to_vec(),
+            transcript: z[16].clone(),
+            player: z[17].clone(),
+            nonce: z[18].clone(),
+            score: z[19].clone(),
+            moves: z[20].clone(),
+            max_tile: z[21].clone(),
+            reached: z[22].clone(),
+            reached_at: z[23].clone(),
+            game_over: z[24].clone(),
+            merges: z[25..25 + NUM_LEVELS].to_vec(),
+            history: z[25 + NUM_LEVELS].clone(),
+        }
+    }
+
+    pub fn to_z(&self) -> Vec<T> {
+        let mut z = self.board.clone();
+        z.push(self.transcript.clone());
+        z.push(self.player.clone());
+        z.push(self.nonce.clone());
+        z.push(self.score.clone());
+        z.push(self.moves.clone());
+        z.push(self.max_tile.clone());
+        z.push(self.reached.clone());
+        z.push(self.reached_at.clone());
+        z.push(self.game_over.clone());
+        z.extend_from_slice(&self.merges);
+        z.push(self.history.clone());
+
+        z
+    }
+
+    /// The number of elements in `z` in private-board mode.
+    pub const PRIVATE_ARITY: usize = 5;
+
+    /// Returns `z` in private-board mode: `commitment` (see `commit_state`),
+    /// the player, the nonce, and the only summaries revealed, the score and
+    /// the max tile.
+    pub fn to_private_z(&self, commitment: T) -> Vec<T> {
+        vec![
+            commitment,
+            self.player.clone(),
+            self.nonce.clone(),
+            self.score.clone(),
+            self.max_tile.clone(),
+        ]
+    }
+
+    /// The index in `z` of the score, the first element after the player and
+    /// the nonce.
+    pub const SUMMARY_INDEX: usize = 19;
+
+    /// The number of elements in `z` in claim mode.
+    pub const CLAIM_ARITY: usize = 6;
+
+    /// Returns `z` in claim mode: `commitment` (see `commit_state`), the
+    /// player, the nonce, the thresholds of the claim, and whether it holds.
+    pub fn to_claim_z(
+        &self,
+        commitment: T,
+        score_threshold: T,
+        max_tile_threshold: T,
+        holds: T,
+    ) -> Vec<T> {
+        vec![
+            commitment,
+            self.player.clone(),
+            self.nonce.clone(),
+            score_threshold,
+            max_tile_threshold,
+            holds,
+        ]
+    }
+
+    /// The number of elements in `z` in packed-board mode.
+    pub const PACKED_ARITY: usize = Self::ARITY - 15;
+
+    /// Returns `z` in packed-board mode: `packed` (see `pack`) followed by
+    /// every element after the board.
+    pub fn to_packed_z(&self, packed: T) -> Vec<T> {
+        let mut z = vec![packed];
+        z.extend_from_slice(&self.to_z()[16..]);
+
+        z
+    }
+
+    /// Rebuilds the state from `z` in packed-board mode and the unpacked board.
+    pub fn from_packed_z(z: &[T], board: &[T]) -> Self {
+        assert_eq!(z.len(), Self::PACKED_ARITY);
+        assert_eq!(board.len(), 16);
+
+        let mut full = board.to_vec();
+        full.extend_from_slice(&z[1..]);
+
+        Self::from_z(&full)
+    }
+}
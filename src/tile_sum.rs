@@ -0,0 +1,122 @@
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use crate::NumConstraintSystem;
+
+/// Enforces sum{new_board} = sum{old_board} + new_number.
+///
+/// Sliding and merging tiles never changes the sum of the board, so every
+/// move only adds the spawned number. This is a safety net against bugs in
+/// `SortByZero`, `Merge` and `Restore`, and can be left out to save constraints.
+pub struct TileSum<F: PrimeField> {
+    pub old_board: Vec<AllocatedNum<F>>,
+
+    pub new_board: Vec<AllocatedNum<F>>,
+
+    pub new_number: AllocatedNum<F>,
+}
+
+impl<F: PrimeField> TileSum<F> {
+    pub fn new(
+        old_board: &[AllocatedNum<F>],
+        new_board: &[AllocatedNum<F>],
+        new_number: &AllocatedNum<F>,
+    ) -> Self {
+        assert_eq!(old_board.len(), 16);
+        assert_eq!(new_board.len(), 16);
+
+        Self {
+            old_board: old_board.to_vec(),
+            new_board: new_board.to_vec(),
+            new_number: new_number.clone(),
+        }
+    }
+
+    pub fn synthesize<CS: ConstraintSystem<F>>(&self, mut cs: CS) -> Result<(), SynthesisError> {
+        let old_sum = AllocatedNum::sum(cs.namespace(|| "sum_of_old_board"), &self.old_board)?;
+        let new_sum = AllocatedNum::sum(cs.namespace(|| "sum_of_new_board"), &self.new_board)?;
+
+        cs.enforce(
+            || "enforce_(new_sum = old_sum + new_number)",
+            |lc| lc + old_sum.get_variable() + self.new_number.get_variable(),
+            |lc| lc + CS::one(),
+            |lc| lc + new_sum.get_variable(),
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+
+    use super::TileSum;
+
+    fn synthesize(old_board: &[Fr], new_board: &[Fr], new_number: Fr) -> bool {
+        let mut cs = TestConstraintSystem::<Fr>::new();
+
+        let mut old_board_vars = Vec::new();
+        for (i, x) in old_board.iter().enumerate() {
+            old_board_vars.push(
+                AllocatedNum::alloc(cs.namespace(|| format!("old_board_{i}")), || Ok(*x)).unwrap(),
+            );
+        }
+
+        let mut new_board_vars = Vec::new();
+        for (i, x) in new_board.iter().enumerate() {
+            new_board_vars.push(
+                AllocatedNum::alloc(cs.namespace(|| format!("new_board_{i}")), || Ok(*x)).unwrap(),
+            );
+        }
+
+        let new_number_var =
+            AllocatedNum::alloc(cs.namespace(|| "new_number"), || Ok(new_number)).unwrap();
+
+        let circuit = TileSum::new(&old_board_vars, &new_board_vars, &new_number_var);
+        circuit.synthesize(&mut cs).unwrap();
+
+        cs.is_satisfied()
+    }
+
+    #[test]
+    fn test_tile_sum() {
+        let zero = Fr::ZERO;
+        let two = Fr::from(2);
+        let four = Fr::from(4);
+        let eight = Fr::from(8);
+
+        #[rustfmt::skip]
+        let old_board = vec![
+            two,   two,   four,  zero,
+            zero,  zero,  zero,  zero,
+            eight, zero,  zero,  eight,
+            zero,  zero,  zero,  zero,
+        ];
+
+        // Moving left and spawning a 2.
+        #[rustfmt::skip]
+        let new_board = vec![
+            four,  four,  zero,  zero,
+            zero,  zero,  zero,  two,
+            Fr::from(16), zero, zero, zero,
+            zero,  zero,  zero,  zero,
+        ];
+
+        assert!(synthesize(&old_board, &new_board, two));
+        assert!(!synthesize(&old_board, &new_board, four));
+
+        // A merge that lost a tile.
+        #[rustfmt::skip]
+        let bad_board = vec![
+            four,  zero,  zero,  zero,
+            zero,  zero,  zero,  two,
+            Fr::from(16), zero, zero, zero,
+            zero,  zero,  zero,  zero,
+        ];
+
+        assert!(!synthesize(&old_board, &bad_board, two));
+    }
+}
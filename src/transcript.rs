@@ -0,0 +1,129 @@
+use bellpepper_core::{num::AllocatedNum, ConstraintSystem, SynthesisError};
+use ff::PrimeField;
+
+use crate::poseidon::hash_circuit;
+
+/// Returns the code of a direction, `1 * up + 2 * down + 3 * left + 4 * right`.
+pub fn direction_code<F: PrimeField, CS: ConstraintSystem<F>>(
+    mut cs: CS,
+    direction: &[AllocatedNum<F>],
+) -> Result<AllocatedNum<F>, SynthesisError> {
+    assert_eq!(direction.len(), 4);
+
+    let mut code = F::ZERO;
+    for (i, x) in direction.iter().enumerate() {
+        code += F::from(i as u64 + 1) * x.get_value().unwrap_or(F::ZERO);
+    }
+    let code_var = AllocatedNum::alloc(cs.namespace(|| "alloc_code"), || Ok(code))?;
+
+    cs.enforce(
+        || "enforce_(code = 1 * up + 2 * down + 3 * left + 4 * right)",
+        |lc| {
+            let mut sum = lc;
+            for (i, x) in direction.iter().enumerate() {
+                sum = sum + (F::from(i as u64 + 1), x.get_variable());
+            }
+
+            sum
+        },
+        |lc| lc + CS::one(),
+        |lc| lc + code_var.get_variable(),
+    );
+
+    Ok(code_var)
+}
+
+/// Absorbs a move into the running transcript hash.
+///
+/// The direction is absorbed as its code (see `direction_code`), so
+/// new_transcript = hash(transcript, code), or
+/// new_transcript = hash(transcript, code, new_index, new_number) if the spawned
+/// number is absorbed too.
+pub struct Transcript<F: PrimeField> {
+    pub transcript: AllocatedNum<F>,
+
+    pub code: AllocatedNum<F>,
+
+    /// The index and the value of the spawned number.
+    pub spawn: Option<(AllocatedNum<F>, AllocatedNum<F>)>,
+}
+
+impl<F: PrimeField> Transcript<F> {
+    pub fn new(
+        transcript: &AllocatedNum<F>,
+        code: &AllocatedNum<F>,
+        spawn: Option<(&AllocatedNum<F>, &AllocatedNum<F>)>,
+    ) -> Self {
+        Self {
+            transcript: transcript.clone(),
+            code: code.clone(),
+            spawn: spawn.map(|(index, number)| (index.clone(), number.clone())),
+        }
+    }
+
+    pub fn synthesize<CS: ConstraintSystem<F>>(
+        &self,
+        mut cs: CS,
+    ) -> Result<AllocatedNum<F>, SynthesisError> {
+        let mut preimage = vec![self.transcript.clone(), self.code.clone()];
+        if let Some((index, number)) = &self.spawn {
+            preimage.push(index.clone());
+            preimage.push(number.clone());
+        }
+
+        hash_circuit(cs.namespace(|| "hash"), &preimage)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bellpepper_core::{num::AllocatedNum, test_cs::TestConstraintSystem, ConstraintSystem};
+    use blstrs::Scalar as Fr;
+    use ff::Field;
+
+    use crate::poseidon::hash;
+
+    use super::{direction_code, Transcript};
+
+    #[test]
+    fn test_transcript() {
+        let zero = Fr::ZERO;
+        let one = Fr::ONE;
+        let transcript = Fr::from(7);
+        let left = [zero, zero, one, zero];
+
+        for with_spawn in [false, true] {
+            let mut cs = TestConstraintSystem::<Fr>::new();
+
+            let transcript_var =
+                AllocatedNum::alloc(cs.namespace(|| "transcript"), || Ok(transcript)).unwrap();
+            let mut direction = vec![];
+            for (i, x) in left.iter().enumerate() {
+                direction.push(
+                    AllocatedNum::alloc(cs.namespace(|| format!("direction_{i}")), || Ok(*x))
+                        .unwrap(),
+                );
+            }
+            let index = AllocatedNum::alloc(cs.namespace(|| "index"), || Ok(Fr::from(5))).unwrap();
+            let number =
+                AllocatedNum::alloc(cs.namespace(|| "number"), || Ok(Fr::from(2))).unwrap();
+
+            let spawn = if with_spawn {
+                Some((&index, &number))
+            } else {
+                None
+            };
+            let code = direction_code(cs.namespace(|| "code"), &direction).unwrap();
+            let circuit = Transcript::new(&transcript_var, &code, spawn);
+            let new_transcript = circuit.synthesize(&mut cs).unwrap();
+            assert!(cs.is_satisfied());
+
+            let expected = if with_spawn {
+                hash(&[transcript, Fr::from(3), Fr::from(5), Fr::from(2)])
+            } else {
+                hash(&[transcript, Fr::from(3)])
+            };
+            assert_eq!(new_transcript.get_value().unwrap(), expected);
+        }
+    }
+}
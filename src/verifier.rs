@@ -0,0 +1,643 @@
+use std::{fmt, path::Path};
+
+use ff::PrimeField;
+use nova_snark::{
+    errors::NovaError,
+    traits::{circuit::StepCircuit, snark::RelaxedR1CSSNARKTrait, Group},
+    CompressedSNARK, VerifierKey,
+};
+
+use crate::{
+    bundle::{BundleError, ProofBundle},
+    circuit::{Game2048Circuit, GameConfig},
+    commitment::StartOpening,
+    game::{is_start_board, try_to_u64, Direction, Game},
+    history::BoardHistory,
+    packing::unpack,
+    params::{load_verifier_key, ParamsError, ParamsKey},
+    prover::GameVerifierKey,
+    state::GameState,
+};
+
+/// The reason a game proof is rejected.
+#[derive(Debug)]
+pub enum VerifyError {
+    /// The nonce in `z0` is not the one issued for this session.
+    WrongNonce,
+
+    /// The final state of the proof is not the one of the published moves.
+    WrongMoves,
+
+    /// The rules do not keep the board in `z`, as in private-board and
+    /// packed-board modes, so the moves cannot be replayed.
+    HiddenBoard,
+
+    /// A step cannot have no move.
+    NoMovesPerStep,
+
+    /// The bundle cannot be decoded.
+    MalformedBundle(BundleError),
+
+    /// The bundle is for another circuit, rules or number of moves per
+    /// step than the verifier key.
+    WrongRules(ParamsKey),
+
+    /// The bundle is for other public parameters than the verifier key.
+    WrongDigest,
+
+    /// `z0` is not the state of a game, or not of a new game where one is
+    /// expected.
+    WrongZ0,
+
+    /// `z0` commits to a state in private-board or claim mode, which is only
+    /// checked with its opening, see `verify_private_game`.
+    UnopenedZ0,
+
+    /// The final state claimed by the bundle is not the one of the proof.
+    WrongOutputs,
+
+    /// The proof does not verify against `z0`.
+    Nova(NovaError),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::WrongNonce => write!(f, "the proof was not made for this session"),
+            VerifyError::WrongMoves => write!(f, "the proof was not made for these moves"),
+            VerifyError::HiddenBoard => {
+                write!(f, "the rules do not reveal the board to replay the moves")
+            }
+            VerifyError::NoMovesPerStep => write!(f, "a step needs at least one move"),
+            VerifyError::MalformedBundle(e) => write!(f, "{}", e),
+            VerifyError::WrongRules(key) => write!(f, "the proof was made for {:?}", key),
+            VerifyError::WrongDigest => {
+                write!(f, "the proof was made for other public parameters")
+            }
+            VerifyError::WrongZ0 => write!(f, "the proof does not start a new game"),
+            VerifyError::UnopenedZ0 => {
+                write!(f, "the proof starts from a commitment which is not opened")
+            }
+            VerifyError::WrongOutputs => {
+                write!(f, "the proof does not output the claimed final state")
+            }
+            VerifyError::Nova(e) => write!(f, "the proof does not verify: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Verifies a game played in the session of `nonce`, and returns its final state.
+///
+/// A proof for another session is rejected even if it is valid, so a proof cannot
+/// be replayed in a later round.
+pub fn verify_session<G1, G2, C2, S1, S2>(
+    snark: &CompressedSNARK<G1, G2, Game2048Circuit<G1::Scalar>, C2, S1, S2>,
+    vk: &VerifierKey<G1, G2, Game2048Circuit<G1::Scalar>, C2, S1, S2>,
+    num_steps: usize,
+    z0_primary: &[G1::Scalar],
+    z0_secondary: &[G2::Scalar],
+    nonce: G1::Scalar,
+) -> Result<GameState<G1::Scalar>, VerifyError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    C2: StepCircuit<G2::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    if z0_primary.len() != GameState::<G1::Scalar>::ARITY {
+        return Err(VerifyError::Nova(NovaError::InvalidInitialInputLength));
+    }
+    if GameState::from_z(z0_primary).nonce != nonce {
+        return Err(VerifyError::WrongNonce);
+    }
+
+    let (zn_primary, _) = snark
+        .verify(vk, num_steps, z0_primary, z0_secondary)
+        .map_err(VerifyError::Nova)?;
+
+    Ok(GameState::from_z(&zn_primary))
+}
+
+/// Verifies that a game played the published `moves` in steps of
+/// `moves_per_step` moves, the last one padded with no-ops like
+/// `GameProver::prove` does, and returns its final state.
+///
+/// The spawned numbers and the transcript only depend on `z0` and the moves, so
+/// replaying the moves with `Game` gives the only final state that a proof of
+/// these moves can have. `config` and `moves_per_step` must be the ones of the
+/// circuit of `vk`, and `config` must keep the board in `z`.
+///
+/// The moves are replayed out of the circuit rather than exposed as public
+/// inputs of a variant of it: the transcript in the final state already binds
+/// the proof to its moves, so the same circuit and public parameters serve
+/// both private and published moves, and `z` does not grow with the moves.
+/// `z0` is rejected with `VerifyError::WrongZ0` unless it is the state of a
+/// game, see `Game::try_from_z`.
+///
+/// Only the moves which change the state are bound: no-ops, and moves played
+/// after the game is over, leave the state and the transcript as they are, so
+/// published moves which differ from the proven ones by such moves verify too.
+pub fn verify_replay<G1, G2, C2, S1, S2>(
+    snark: &CompressedSNARK<G1, G2, Game2048Circuit<G1::Scalar>, C2, S1, S2>,
+    vk: &VerifierKey<G1, G2, Game2048Circuit<G1::Scalar>, C2, S1, S2>,
+    z0_primary: &[G1::Scalar],
+    z0_secondary: &[G2::Scalar],
+    config: GameConfig,
+    moves_per_step: usize,
+    moves: &[Direction],
+) -> Result<GameState<G1::Scalar>, VerifyError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    C2: StepCircuit<G2::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    if config.private_board || config.packed_board {
+        return Err(VerifyError::HiddenBoard);
+    }
+    if moves_per_step == 0 {
+        return Err(VerifyError::NoMovesPerStep);
+    }
+
+    let mut game = Game::try_from_z(z0_primary, config).ok_or(VerifyError::WrongZ0)?;
+    for direction in moves {
+        game.play(*direction);
+    }
+
+    // The no-ops of the padding leave the state as it is.
+    let num_steps = moves.len().div_ceil(moves_per_step);
+    let (zn_primary, _) = snark
+        .verify(vk, num_steps, z0_primary, z0_secondary)
+        .map_err(VerifyError::Nova)?;
+
+    let state = GameState::from_z(&zn_primary);
+    if state != game.state() {
+        return Err(VerifyError::WrongMoves);
+    }
+
+    Ok(state)
+}
+
+/// A verifier key with the key and the digest of the public parameters it
+/// was generated from, see `load_verifier_key`.
+pub struct GameVerifier<G1, G2, S1, S2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    pub vk: GameVerifierKey<G1, G2, S1, S2>,
+
+    pub key: ParamsKey,
+
+    pub digest: G1::Scalar,
+}
+
+impl<G1, G2, S1, S2> GameVerifier<G1, G2, S1, S2>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    pub fn new(vk: GameVerifierKey<G1, G2, S1, S2>, key: ParamsKey, digest: G1::Scalar) -> Self {
+        Self { vk, key, digest }
+    }
+
+    /// Loads a verifier key saved by `save_verifier_key`.
+    pub fn load(path: &Path, key: ParamsKey) -> Result<Self, ParamsError> {
+        let (vk, digest) = load_verifier_key(path, &key)?;
+        Ok(Self::new(vk, key, digest))
+    }
+}
+
+/// The state `z` of a game, as much of it as the mode of the circuit reveals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevealedState<F> {
+    /// The whole state, in the public and packed-board modes.
+    Public(GameState<F>),
+
+    /// The state in private-board mode, see `GameConfig::private_board`.
+    /// `commitment` hides the rest of the state, see `commit_state`.
+    Private {
+        commitment: F,
+        player: F,
+        nonce: F,
+        score: F,
+        max_tile: F,
+    },
+
+    /// The state in claim mode, see `GameConfig::claim`.
+    Claim {
+        commitment: F,
+        player: F,
+        nonce: F,
+        score_threshold: F,
+        max_tile_threshold: F,
+        holds: bool,
+    },
+}
+
+impl<F: PrimeField> RevealedState<F> {
+    /// Decodes `z` in the mode of `config`, or returns `None` if it is not a
+    /// state of that mode.
+    pub fn from_z(z: &[F], config: GameConfig) -> Option<Self> {
+        if config.claim {
+            if z.len() != GameState::<F>::CLAIM_ARITY {
+                return None;
+            }
+
+            Some(RevealedState::Claim {
+                commitment: z[0],
+                player: z[1],
+                nonce: z[2],
+                score_threshold: z[3],
+                max_tile_threshold: z[4],
+                holds: z[5] == F::ONE,
+            })
+        } else if config.private_board {
+            if z.len() != GameState::<F>::PRIVATE_ARITY {
+                return None;
+            }
+
+            Some(RevealedState::Private {
+                commitment: z[0],
+                player: z[1],
+                nonce: z[2],
+                score: z[3],
+                max_tile: z[4],
+            })
+        } else if config.packed_board {
+            if z.len() != GameState::<F>::PACKED_ARITY {
+                return None;
+            }
+
+            let board = unpack(z[0])?;
+            Some(RevealedState::Public(GameState::from_packed_z(z, &board)))
+        } else {
+            if z.len() != GameState::<F>::ARITY {
+                return None;
+            }
+
+            Some(RevealedState::Public(GameState::from_z(z)))
+        }
+    }
+
+    /// Returns true if this is the state of a new game: zero counters and a
+    /// board `is_start_board` accepts. The committed state of the private-board
+    /// and claim modes cannot be checked this way, so it is never taken for a
+    /// new game, see `StartOpening`.
+    pub fn is_new_game(&self, config: GameConfig) -> bool {
+        let state = match self {
+            RevealedState::Public(state) => state,
+            RevealedState::Private { .. } | RevealedState::Claim { .. } => return false,
+        };
+
+        let history = if config.board_history {
+            BoardHistory::<F>::empty_root()
+        } else {
+            F::ZERO
+        };
+
+        let board: Option<Vec<_>> = state.board.iter().map(|x| try_to_u64(*x)).collect();
+        let start_board = board.is_some_and(|board| is_start_board(&board.try_into().unwrap()));
+
+        start_board
+            && state.transcript == F::ZERO
+            && state.score == F::ZERO
+            && state.moves == F::ZERO
+            && state.max_tile == F::ZERO
+            && state.reached == F::ZERO
+            && state.reached_at == F::ZERO
+            && state.game_over == F::ZERO
+            && state.merges.iter().all(|x| *x == F::ZERO)
+            && state.history == history
+    }
+}
+
+/// Verifies an encoded `ProofBundle` of a new game, and returns its final
+/// state.
+///
+/// In private-board and claim modes `z0` is a commitment, so the bundle is
+/// rejected with `VerifyError::UnopenedZ0`, see `verify_private_game`.
+pub fn verify_game<G1, G2, S1, S2>(
+    bundle: &[u8],
+    verifier: &GameVerifier<G1, G2, S1, S2>,
+) -> Result<RevealedState<G1::Scalar>, VerifyError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    verify_bundle(bundle, verifier, None)
+}
+
+/// Verifies an encoded `ProofBundle` of a new game in private-board or claim
+/// mode, whose `z0` is opened by `opening`, and returns its final state. The
+/// prover gets the opening from `GameProver::start_opening`.
+pub fn verify_private_game<G1, G2, S1, S2>(
+    bundle: &[u8],
+    verifier: &GameVerifier<G1, G2, S1, S2>,
+    opening: &StartOpening<G1::Scalar>,
+) -> Result<RevealedState<G1::Scalar>, VerifyError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    verify_bundle(bundle, verifier, Some(opening))
+}
+
+fn verify_bundle<G1, G2, S1, S2>(
+    bundle: &[u8],
+    verifier: &GameVerifier<G1, G2, S1, S2>,
+    opening: Option<&StartOpening<G1::Scalar>>,
+) -> Result<RevealedState<G1::Scalar>, VerifyError>
+where
+    G1: Group<Base = <G2 as Group>::Scalar>,
+    G2: Group<Base = <G1 as Group>::Scalar>,
+    S1: RelaxedR1CSSNARKTrait<G1>,
+    S2: RelaxedR1CSSNARKTrait<G2>,
+{
+    let bundle =
+        ProofBundle::<G1, G2, S1, S2>::decode(bundle, &verifier.key).map_err(|e| match e {
+            BundleError::WrongKey(key) => VerifyError::WrongRules(key),
+            e => VerifyError::MalformedBundle(e),
+        })?;
+    if bundle.digest != verifier.digest {
+        return Err(VerifyError::WrongDigest);
+    }
+
+    let config = verifier.key.config;
+    let z0 = &bundle.proof.z0_primary;
+    let new_game = match opening {
+        Some(opening) => opening.opens_new_game(z0, config),
+        None if config.private_board => return Err(VerifyError::UnopenedZ0),
+        None => RevealedState::from_z(z0, config).is_some_and(|z0| z0.is_new_game(config)),
+    };
+    if !new_game {
+        return Err(VerifyError::WrongZ0);
+    }
+
+    let zn_primary = bundle
+        .proof
+        .verify(&verifier.vk)
+        .map_err(VerifyError::Nova)?;
+    if zn_primary != bundle.proof.zn_primary {
+        return Err(VerifyError::WrongOutputs);
+    }
+
+    RevealedState::from_z(&zn_primary, config).ok_or(VerifyError::WrongOutputs)
+}
+
+#[cfg(test)]
+mod test {
+    use ff::Field;
+    use nova_snark::{
+        traits::{circuit::TrivialCircuit, snark::default_ck_hint, Group},
+        CompressedSNARK, PublicParams, RecursiveSNARK,
+    };
+
+    use crate::{
+        bundle::ProofBundle,
+        circuit::{Game2048Circuit, GameConfig},
+        game::{Direction, Game},
+        params::ParamsKey,
+        prover::GameProver,
+        state::GameState,
+    };
+
+    use super::{
+        verify_game, verify_private_game, verify_replay, verify_session, GameVerifier,
+        RevealedState, VerifyError,
+    };
+
+    type E1 = pasta_curves::pallas::Point;
+    type E2 = pasta_curves::vesta::Point;
+    type F1 = <E1 as Group>::Scalar;
+    type F2 = <E2 as Group>::Scalar;
+    type EE1 = nova_snark::provider::ipa_pc::EvaluationEngine<E1>;
+    type EE2 = nova_snark::provider::ipa_pc::EvaluationEngine<E2>;
+    type S1 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E1, EE1>;
+    type S2 = nova_snark::spartan::snark::RelaxedR1CSSNARK<E2, EE2>;
+
+    #[rustfmt::skip]
+    const BOARD: [u64; 16] = [
+        0, 0, 2, 0,
+        0, 4, 0, 0,
+        0, 0, 0, 0,
+        0, 0, 0, 0,
+    ];
+
+    /// Changes the state `z` with `f`.
+    fn edit_z(z: &mut Vec<F1>, f: impl FnOnce(&mut GameState<F1>)) {
+        let mut state = GameState::from_z(z);
+        f(&mut state);
+        *z = state.to_z();
+    }
+
+    #[test]
+    fn test_verify_session() {
+        let nonce = F1::from(42);
+        let mut game = Game::<F1>::new(BOARD, GameConfig::default()).with_nonce(nonce);
+        let z0_primary = game.state().to_z();
+        let z0_secondary = vec![F2::ZERO];
+
+        let circuit_primary = Game2048Circuit::new(&[Direction::Left.to_bits()]);
+        let circuit_secondary = TrivialCircuit::default();
+
+        let pp = PublicParams::<E1, E2, _, _>::setup(
+            &circuit_primary,
+            &circuit_secondary,
+            &*default_ck_hint(),
+            &*default_ck_hint(),
+        );
+        let mut recursive_snark = RecursiveSNARK::new(
+            &pp,
+            &circuit_primary,
+            &circuit_secondary,
+            &z0_primary,
+            &z0_secondary,
+        )
+        .unwrap();
+        recursive_snark
+            .prove_step(&pp, &circuit_primary, &circuit_secondary)
+            .unwrap();
+
+        let (pk, vk) = CompressedSNARK::<_, _, _, _, S1, S2>::setup(&pp).unwrap();
+        let snark = CompressedSNARK::prove(&pp, &pk, &recursive_snark).unwrap();
+
+        game.play(Direction::Left);
+        let state = verify_session(&snark, &vk, 1, &z0_primary, &z0_secondary, nonce).unwrap();
+        assert_eq!(state, game.state());
+
+        // The same proof is rejected in another session.
+        let res = verify_session(&snark, &vk, 1, &z0_primary, &z0_secondary, F1::from(43));
+        assert!(matches!(res, Err(VerifyError::WrongNonce)));
+
+        // Claiming another nonce in `z0` breaks the proof.
+        let mut z0_forged = z0_primary.clone();
+        edit_z(&mut z0_forged, |state| state.nonce = F1::from(43));
+        let res = verify_session(&snark, &vk, 1, &z0_forged, &z0_secondary, F1::from(43));
+        assert!(matches!(res, Err(VerifyError::Nova(_))));
+
+        // The proof replays the move it was made for, and no other.
+        let config = GameConfig::default();
+        let replay = |z0: &[F1], config, moves_per_step, moves: &[Direction]| {
+            verify_replay(
+                &snark,
+                &vk,
+                z0,
+                &z0_secondary,
+                config,
+                moves_per_step,
+                moves,
+            )
+        };
+        let state = replay(&z0_primary, config, 1, &[Direction::Left]);
+        assert_eq!(state.unwrap(), game.state());
+
+        let res = replay(&z0_primary, config, 1, &[Direction::Right]);
+        assert!(matches!(res, Err(VerifyError::WrongMoves)));
+
+        // Two moves are two steps of one move.
+        let res = replay(&z0_primary, config, 1, &[Direction::Left, Direction::Noop]);
+        assert!(matches!(res, Err(VerifyError::Nova(_))));
+        let res = replay(&z0_primary, config, 0, &[Direction::Left]);
+        assert!(matches!(res, Err(VerifyError::NoMovesPerStep)));
+
+        // Rules without the board in `z` cannot be replayed.
+        let packed = GameConfig {
+            packed_board: true,
+            ..config
+        };
+        let res = replay(&z0_primary, packed, 1, &[Direction::Left]);
+        assert!(matches!(res, Err(VerifyError::HiddenBoard)));
+
+        // A `z0` which is not the state of a game is rejected before replaying it.
+        let res = replay(&z0_primary[1..], config, 1, &[Direction::Left]);
+        assert!(matches!(res, Err(VerifyError::WrongZ0)));
+        let mut z0_forged = z0_primary.clone();
+        edit_z(&mut z0_forged, |state| state.board[0] = -F1::ONE);
+        let res = replay(&z0_forged, config, 1, &[Direction::Left]);
+        assert!(matches!(res, Err(VerifyError::WrongZ0)));
+    }
+
+    #[test]
+    fn test_verify_game() {
+        let config = GameConfig::default();
+        let prover = GameProver::<E1, E2>::setup(config, 1);
+        let mut game = Game::<F1>::new(BOARD, config);
+        let proof = prover.prove(game.clone(), [Direction::Left]).unwrap();
+        let (pk, vk) = prover.compressed_keys::<S1, S2>().unwrap();
+        let proof = prover.compress(&pk, &proof).unwrap();
+
+        let key = prover.params_key();
+        let digest = prover.public_params().digest();
+        let bytes = ProofBundle::new(key.clone(), digest, proof).encode();
+        let mut verifier = GameVerifier::new(vk, key.clone(), digest);
+
+        game.play(Direction::Left);
+        let state = verify_game(&bytes, &verifier).unwrap();
+        assert_eq!(state, RevealedState::Public(game.state()));
+
+        let res = verify_game(&bytes[..bytes.len() / 2], &verifier);
+        assert!(matches!(res, Err(VerifyError::MalformedBundle(_))));
+
+        verifier.key = ParamsKey::new::<E1>(config, 2);
+        let res = verify_game(&bytes, &verifier);
+        assert!(matches!(res, Err(VerifyError::WrongRules(k)) if k == key));
+        verifier.key = key.clone();
+
+        verifier.digest = F1::ONE;
+        let res = verify_game(&bytes, &verifier);
+        assert!(matches!(res, Err(VerifyError::WrongDigest)));
+        verifier.digest = digest;
+
+        type Bundle = ProofBundle<E1, E2, S1, S2>;
+        let forge = |f: &dyn Fn(&mut Bundle)| {
+            let mut bundle = Bundle::decode(&bytes, &key).unwrap();
+            f(&mut bundle);
+            verify_game(&bundle.encode(), &verifier)
+        };
+
+        // A game which does not start from scratch.
+        let res = forge(&|bundle| {
+            edit_z(&mut bundle.proof.z0_primary, |state| {
+                state.score = F1::from(100)
+            })
+        });
+        assert!(matches!(res, Err(VerifyError::WrongZ0)));
+
+        // A game which starts from a board no new game has.
+        let res = forge(&|bundle| {
+            edit_z(&mut bundle.proof.z0_primary, |state| {
+                state.board = vec![F1::from(1024); 16]
+            })
+        });
+        assert!(matches!(res, Err(VerifyError::WrongZ0)));
+
+        // A new game, but not the one which was proven.
+        let res = forge(&|bundle| {
+            edit_z(&mut bundle.proof.z0_primary, |state| {
+                state.nonce = F1::from(43)
+            })
+        });
+        assert!(matches!(res, Err(VerifyError::Nova(_))));
+
+        let res = forge(&|bundle| {
+            edit_z(&mut bundle.proof.zn_primary, |state| {
+                state.score = F1::from(100)
+            })
+        });
+        assert!(matches!(res, Err(VerifyError::WrongOutputs)));
+    }
+
+    #[test]
+    fn test_verify_private_game() {
+        let config = GameConfig {
+            private_board: true,
+            ..GameConfig::default()
+        };
+        let prover = GameProver::<E1, E2>::setup(config, 1).with_blinding_seed(F1::from(7));
+        let mut game = Game::<F1>::new(BOARD, config);
+        let opening = prover.start_opening(&game).unwrap();
+        let proof = prover.prove(game.clone(), [Direction::Left]).unwrap();
+        let (pk, vk) = prover.compressed_keys::<S1, S2>().unwrap();
+        let proof = prover.compress(&pk, &proof).unwrap();
+
+        let key = prover.params_key();
+        let digest = prover.public_params().digest();
+        let bytes = ProofBundle::new(key.clone(), digest, proof).encode();
+        let verifier = GameVerifier::new(vk, key, digest);
+
+        // The committed state of `z0` is only checked with its opening.
+        let res = verify_game(&bytes, &verifier);
+        assert!(matches!(res, Err(VerifyError::UnopenedZ0)));
+
+        game.play(Direction::Left);
+        let state = verify_private_game(&bytes, &verifier, &opening).unwrap();
+        assert_eq!(
+            state,
+            RevealedState::Private {
+                commitment: prover.z(&game, 1).unwrap()[0],
+                player: F1::ZERO,
+                nonce: F1::ZERO,
+                score: F1::from(game.score),
+                max_tile: F1::from(game.max_tile),
+            }
+        );
+
+        let mut forged = opening.clone();
+        forged.blinding = F1::ONE;
+        let res = verify_private_game(&bytes, &verifier, &forged);
+        assert!(matches!(res, Err(VerifyError::WrongZ0)));
+    }
+}